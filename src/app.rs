@@ -1,42 +1,98 @@
-use ggez::event::{EventHandler, KeyCode, KeyMods};
+use ggez::event::{EventHandler, KeyCode, KeyMods, MouseButton};
 use ggez::graphics::{self, Color};
+use ggez::input::keyboard;
 use ggez::timer;
 use ggez::{Context, GameResult};
 
 use assets::Assets;
-use gamestate::GameState;
+use gamestate::{GameState, Ghost, SimulationMode, TICKS_PER_SECOND};
+use gui;
 use input::InputHandler;
 use renderer;
+use std::time::Duration;
 
-const DESIRED_FPS: u32 = 60;
+const NOTICE_SECS: u64 = 3;
 
 pub struct App<'a, 'b> {
     input_handler: InputHandler,
     game_state: GameState<'a, 'b>,
     assets: Assets,
+    ghosts: Vec<Ghost>,
+    map_meshes: renderer::MapMeshes,
+    echo_fade: Duration,
+    // Shown at the top of the screen for as long as is left of it.
+    notice: Option<(String, Duration)>,
 }
 
 impl<'a, 'b> App<'a, 'b> {
-    pub fn new(ctx: &mut Context) -> GameResult<App<'a, 'b>> {
+    pub fn new(ctx: &mut Context, seed: u64) -> GameResult<App<'a, 'b>> {
         Ok(App {
             input_handler: InputHandler::default(),
-            game_state: GameState::new(),
+            game_state: GameState::new(seed),
             assets: Assets::new(ctx)?,
+            ghosts: Vec::new(),
+            map_meshes: renderer::MapMeshes::default(),
+            echo_fade: Duration::from_millis(renderer::DEFAULT_ECHO_FADE_MS),
+            notice: None,
         })
     }
+
+    // How long positions being rewound through linger on screen.
+    pub fn set_echo_fade(&mut self, fade: Duration) {
+        self.echo_fade = fade;
+    }
+
+    pub fn set_simulation_mode(&mut self, mode: SimulationMode) {
+        self.game_state.set_simulation_mode(mode);
+    }
 }
 
 impl<'a, 'b> EventHandler for App<'a, 'b> {
     fn update(&mut self, ctx: &mut Context) -> GameResult {
-        while timer::check_update_time(ctx, DESIRED_FPS) {
-            self.game_state.update(timer::get_delta(ctx));
+        let tick = Duration::from_secs(1) / TICKS_PER_SECOND;
+        while timer::check_update_time(ctx, TICKS_PER_SECOND) {
+            self.game_state.tick();
+            self.notice = self
+                .notice
+                .take()
+                .and_then(|(text, left)| left.checked_sub(tick).map(|left| (text, left)));
+        }
+        let paradoxes = self.game_state.take_paradoxes();
+        let text = match paradoxes.len() {
+            0 => None,
+            1 => Some("PARADOX: a clone strayed from its recording".to_string()),
+            count => Some(format!(
+                "PARADOX: {} clones strayed from their recordings",
+                count
+            )),
+        };
+        if let Some(text) = text {
+            self.notice = Some((text, Duration::from_secs(NOTICE_SECS)));
         }
         Ok(())
     }
 
     fn draw(&mut self, ctx: &mut Context) -> GameResult {
         graphics::clear(ctx, Color::from([0.0, 0.0, 0.0, 1.0]));
+        renderer::render_map(ctx, self.game_state.get_world(), &mut self.map_meshes)?;
+        renderer::render_echoes(
+            ctx,
+            self.game_state.get_world(),
+            &self.assets,
+            self.echo_fade,
+        )?;
         renderer::render(ctx, self.game_state.get_world(), &self.assets)?;
+        renderer::render_ghosts(ctx, self.game_state.get_world(), &self.ghosts, &self.assets)?;
+        gui::draw_hud(ctx, self.game_state.get_world())?;
+        if let Some((ref text, _)) = self.notice {
+            gui::draw_notice(ctx, text)?;
+        }
+        if self.input_handler.is_timeline_shown() {
+            gui::draw_timeline_overlay(ctx, self.game_state.get_world())?;
+        }
+        if self.game_state.is_paused() {
+            gui::draw_pause_overlay(ctx)?;
+        }
         graphics::present(ctx)?;
         timer::yield_now();
         Ok(())
@@ -46,7 +102,45 @@ impl<'a, 'b> EventHandler for App<'a, 'b> {
         let command = self
             .input_handler
             .key_down_event(ctx, key, mods.into(), rpt);
+        self.game_state.set_paused(self.input_handler.is_paused());
+        self.game_state
+            .set_recording(self.input_handler.is_recording());
+        if command.is_some() {
+            self.ghosts.clear();
+        }
+        self.game_state.queue_command(command);
+        if let Some(control) = self.input_handler.take_time_control() {
+            self.game_state.set_time_control(control);
+        }
+        if let Some(command) = self.input_handler.take_preview() {
+            self.ghosts = self.game_state.preview(command).ghosts;
+        }
+        if self.input_handler.take_spawn_clone() && self.game_state.spawn_clone().is_none() {
+            info!("rewind to where the recording started to bring in a clone");
+        }
+    }
+
+    fn mouse_button_down_event(&mut self, ctx: &mut Context, button: MouseButton, x: f32, y: f32) {
+        let mods = keyboard::active_mods(ctx);
+        let command = self
+            .input_handler
+            .mouse_button_down_event(ctx, button, mods.into(), x, y);
         self.game_state.queue_command(command);
+        if let Some((x, y)) = self.input_handler.take_seek() {
+            if self.input_handler.is_timeline_shown() {
+                if let Some(instant) = gui::timeline_instant(ctx, self.game_state.get_world(), x, y)
+                {
+                    self.game_state.seek(instant);
+                }
+            }
+        }
+    }
+
+    fn key_up_event(&mut self, _ctx: &mut Context, key: KeyCode, _mods: KeyMods) {
+        self.input_handler.key_up_event(key);
+        if let Some(control) = self.input_handler.take_time_control() {
+            self.game_state.set_time_control(control);
+        }
     }
 
     fn resize_event(&mut self, ctx: &mut Context, width: u32, height: u32) {
@@ -0,0 +1,134 @@
+use specs::prelude::*;
+
+use super::command::GameCommand;
+use super::dungeon::Dungeon;
+use super::legs::Legs;
+use super::physics::Position;
+use super::stats::{Equipment, Stats};
+use super::time::*;
+
+pub const BASE_MOVE_DURATION_MS: u64 = 250;
+
+// Everything that decides how long an action takes; shared by the player and monsters.
+#[derive(SystemData)]
+pub struct ActionCosts<'a> {
+    stats: ReadStorage<'a, Stats>,
+    equipment: ReadStorage<'a, Equipment>,
+    legs: ReadStorage<'a, Legs>,
+    dungeon: Read<'a, Dungeon>,
+}
+
+impl<'a> ActionCosts<'a> {
+    // Moves are priced by the terrain at `destination`, if there is one.
+    pub fn duration(
+        &self,
+        entity: Entity,
+        command: GameCommand,
+        destination: Option<&Position>,
+    ) -> Duration {
+        match command {
+            GameCommand::Move(_) => self.move_duration(entity, destination),
+            GameCommand::Stop => Duration::from_secs(0),
+        }
+    }
+
+    fn move_duration(&self, entity: Entity, destination: Option<&Position>) -> Duration {
+        let default_stats = Stats::default();
+        let stats = self.stats.get(entity).unwrap_or(&default_stats);
+        let legs = self.legs.get(entity).cloned().unwrap_or_default();
+        let terrain = destination
+            .and_then(|position| {
+                self.dungeon
+                    .level(position.z())
+                    .map(|level| level.map.get(position.tile()).move_factor(legs))
+            })
+            .unwrap_or_default();
+        let factor = stats.speed_factor()
+            * stats.encumbrance(self.equipment.get(entity)).factor()
+            * legs.move_factor()
+            * terrain;
+        factor.scale(Duration::from_millis(BASE_MOVE_DURATION_MS))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::dungeon::Level;
+    use super::super::map::{TileMap, TileType};
+    use super::super::physics::Direction;
+    use super::*;
+
+    #[test]
+    fn move_costs() {
+        let mut world = World::new();
+        world.register::<Stats>();
+        world.register::<Equipment>();
+        world.register::<Legs>();
+        world.add_resource(Dungeon::new());
+        let plain = world.create_entity().build();
+        let fast = world.create_entity().with(Stats::new(200, 50)).build();
+        let burdened = world
+            .create_entity()
+            .with(Stats::new(100, 10))
+            .with(Equipment::new(8))
+            .build();
+        let slow_dog = world
+            .create_entity()
+            .with(Stats::new(50, 50))
+            .with(Equipment::new(100))
+            .with(Legs::Quadrupedal)
+            .build();
+
+        let costs = ActionCosts::fetch(&world.res);
+        let step = GameCommand::Move(Direction::E);
+        assert_eq!(
+            costs.duration(plain, step, None),
+            Duration::from_millis(250)
+        );
+        assert_eq!(costs.duration(fast, step, None), Duration::from_millis(125));
+        assert_eq!(
+            costs.duration(burdened, step, None),
+            Duration::from_nanos(312_500_000)
+        );
+        assert_eq!(
+            costs.duration(slow_dog, step, None),
+            Duration::from_millis(600)
+        );
+    }
+
+    #[test]
+    fn terrain_costs() {
+        let mut world = World::new();
+        world.register::<Stats>();
+        world.register::<Equipment>();
+        world.register::<Legs>();
+        let mut map = TileMap::new(4, 3, TileType::Floor);
+        map.set((2, 1), TileType::Water);
+        let mut dungeon = Dungeon::new();
+        dungeon.push(Level {
+            map,
+            stairs_up: (0, 0),
+            stairs_down: (3, 2),
+        });
+        world.add_resource(dungeon);
+        let snake = world.create_entity().with(Legs::Slithering).build();
+        let bird = world.create_entity().with(Legs::Winged).build();
+
+        let costs = ActionCosts::fetch(&world.res);
+        let step = GameCommand::Move(Direction::E);
+        let floor = Position::new(1, 1, Direction::E);
+        let water = Position::new(2, 1, Direction::E);
+        assert_eq!(
+            costs.duration(snake, step, Some(&floor)),
+            Duration::from_nanos(312_500_000)
+        );
+        assert_eq!(
+            costs.duration(snake, step, Some(&water)),
+            Duration::from_millis(625)
+        );
+        assert_eq!(
+            costs.duration(bird, step, Some(&water)),
+            Duration::from_nanos(187_500_000)
+        );
+    }
+}
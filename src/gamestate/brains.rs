@@ -1,13 +1,20 @@
 use specs::prelude::*;
 use std::marker::PhantomData;
 
+use super::actions::ActionCosts;
+use super::claims::{ClaimOutcome, TileClaims};
+use super::clones::CommandRecorder;
 use super::command::*;
+use super::dungeon::Dungeon;
+use super::history::{Historical, Journal};
+use super::legs::Legs;
 use super::physics::*;
 use super::time::*;
+use super::timeline::Timeline;
 
 pub fn module_systems<'a, 'b>(builder: DispatcherBuilder<'a, 'b>) -> DispatcherBuilder<'a, 'b> {
     builder
-        .with(PlayerCommands, "player_commands", &[])
+        .with(PlayerCommands, "player_commands", &["local_time"])
         .with(
             TimingSystem::<PlayerBrain>::new(),
             "player_brain_timing",
@@ -48,9 +55,8 @@ where
     );
 
     fn run(&mut self, (time, mut entity_s, mut brain_s, brain_timing): Self::SystemData) {
-        let delta = time.delta();
         for (entity, brain, _) in (&*entity_s, &mut brain_s, brain_timing.scheduled()).join() {
-            brain.think(delta, entity);
+            brain.think(time.local_delta(&entity), entity);
         }
     }
 
@@ -60,10 +66,12 @@ where
     }
 }
 
-#[derive(Component, Debug)]
+#[derive(Component, Debug, Clone)]
 #[storage(HashMapStorage)]
 pub struct PlayerBrain {}
 
+impl Historical for PlayerBrain {}
+
 impl Brain for PlayerBrain {
     fn think(&mut self, delta: DirectedTime, entity: Entity) {
         trace!("{:?} is thinking... {:?}", entity, delta);
@@ -72,42 +80,164 @@ impl Brain for PlayerBrain {
 
 impl Timed for PlayerBrain {}
 
+// Everything needed to carry out a command on an entity's behalf.
+#[derive(SystemData)]
+pub struct Actor<'a> {
+    pub time: Write<'a, Timekeeper>,
+    pub timeline: Write<'a, Timeline>,
+    movable_timing: Write<'a, TimingData<Movable>>,
+    movable_journal: Write<'a, Journal<Movable>>,
+    pub position: ReadStorage<'a, Position>,
+    legs: ReadStorage<'a, Legs>,
+    dungeon: Read<'a, Dungeon>,
+    claims: Write<'a, TileClaims>,
+    costs: ActionCosts<'a>,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Executed {
+    Moving(Duration),
+    // Still takes its time, but someone else got the tile first.
+    Bumped(Duration),
+    // How much of the interrupted action went unused.
+    Stopped(Duration),
+    Refused,
+}
+
+impl<'a> Actor<'a> {
+    // Entities that lose their tiles are pushed onto `bumped`, to be bumped once `movable`
+    // storage is free again.
+    pub fn execute(
+        &mut self,
+        entity: Entity,
+        movable: &mut Movable,
+        command: GameCommand,
+        bumped: &mut Vec<Entity>,
+    ) -> Executed {
+        self.claims.prune(self.time.rewind_limit());
+        let before = movable.clone();
+        let executed = match command {
+            GameCommand::Move(direction) => {
+                let destination = match self.position.get(entity) {
+                    Some(position) => {
+                        let legs = self.legs.get(entity).cloned().unwrap_or_default();
+                        match self.dungeon.destination(position, direction, legs) {
+                            Some(destination) => Some(destination),
+                            None => {
+                                info!("{:?}: can't move {:?}", entity, direction);
+                                return Executed::Refused;
+                            }
+                        }
+                    }
+                    None => None,
+                };
+                let duration = self.costs.duration(entity, command, destination.as_ref());
+                info!("{:?}: move {:?}", entity, direction);
+                match movable.start_moving(
+                    &entity,
+                    &self.time,
+                    &self.timeline,
+                    &mut self.movable_timing,
+                    direction,
+                    duration,
+                ) {
+                    Some(duration) => match destination {
+                        Some(destination) => match self.claims.claim(
+                            &self.timeline,
+                            destination.cell(),
+                            entity,
+                            self.time.span(duration),
+                            self.timeline.moment(&self.time),
+                        ) {
+                            ClaimOutcome::Granted { bumped: mut losers } => {
+                                bumped.append(&mut losers);
+                                Executed::Moving(duration)
+                            }
+                            ClaimOutcome::Contested { .. } => {
+                                movable.bump();
+                                Executed::Bumped(duration)
+                            }
+                        },
+                        None => Executed::Moving(duration),
+                    },
+                    None => Executed::Refused,
+                }
+            }
+            GameCommand::Stop => {
+                match movable.stop(
+                    &entity,
+                    &self.time,
+                    &self.timeline,
+                    &mut self.movable_timing,
+                ) {
+                    Some(unused) => {
+                        self.claims.release(
+                            &self.timeline,
+                            entity,
+                            self.time.now(),
+                            self.timeline.moment(&self.time),
+                        );
+                        Executed::Stopped(unused)
+                    }
+                    None => Executed::Refused,
+                }
+            }
+        };
+        if executed != Executed::Refused {
+            self.movable_journal.record(
+                self.timeline.moment(&self.time),
+                entity,
+                Some(before),
+                Some(movable.clone()),
+            );
+        }
+        executed
+    }
+
+    pub fn bump(&mut self, bumped: Vec<Entity>, movable: &mut WriteStorage<Movable>) {
+        let moment = self.timeline.moment(&self.time);
+        for entity in bumped {
+            self.movable_journal
+                .modify(moment, entity, movable, |movable| movable.bump());
+        }
+    }
+}
+
 struct PlayerCommands;
 
 #[derive(SystemData)]
 struct PlayerCommandsData<'a> {
-    time: Write<'a, Timekeeper>,
+    actor: Actor<'a>,
     commands: Write<'a, GameCommandQueue>,
+    recorder: Write<'a, CommandRecorder>,
     entity: Entities<'a>,
-    brain: WriteStorage<'a, PlayerBrain>,
-    brain_timing: Write<'a, TimingData<PlayerBrain>>,
+    brain: ReadStorage<'a, PlayerBrain>,
     movable: WriteStorage<'a, Movable>,
-    movable_timing: Write<'a, TimingData<Movable>>,
 }
 
 impl<'a> System<'a> for PlayerCommands {
     type SystemData = PlayerCommandsData<'a>;
 
     fn run(&mut self, mut data: Self::SystemData) {
-        for (entity, mut brain, mut movable) in
-            (&*data.entity, &mut data.brain, &mut data.movable).join()
-        {
+        let mut bumped = Vec::new();
+        for (entity, _, movable) in (&*data.entity, &data.brain, &mut data.movable).join() {
+            // Commands issued in the past have already been forked off for, see
+            // `GameState::fork_for_commands()`.
             while let Some(command) = data.commands.pop() {
-                match command {
-                    GameCommand::Move(direction) => {
-                        let duration = Duration::from_millis(250);
-                        data.time.add_simulation_time(duration);
-                        info!("Move {:?}", direction);
-                        movable.start_moving(
-                            &entity,
-                            &data.time,
-                            &mut data.movable_timing,
-                            direction,
-                            duration,
-                        );
+                let now = data.actor.time.now();
+                let position = data.actor.position.get(entity).cloned();
+                let executed = data.actor.execute(entity, movable, command, &mut bumped);
+                match executed {
+                    Executed::Moving(duration) | Executed::Bumped(duration) => {
+                        data.actor.time.add_simulation_time(duration)
                     }
+                    Executed::Stopped(unused) => data.actor.time.refund_simulation_time(unused),
+                    Executed::Refused => (),
                 }
+                data.recorder
+                    .record(entity, now, command, position, executed);
             }
         }
+        data.actor.bump(bumped, &mut data.movable);
     }
 }
@@ -0,0 +1,151 @@
+use specs::prelude::*;
+use std::collections::HashMap;
+
+use super::dungeon::Dungeon;
+use super::history::Historical;
+use super::physics::Position;
+use super::time::*;
+
+pub fn module_systems<'a, 'b>(builder: DispatcherBuilder<'a, 'b>) -> DispatcherBuilder<'a, 'b> {
+    builder.with(LocalTimeSystem, "local_time", &["active_level"])
+}
+
+// Haste, slow or stasis of a single entity.
+#[derive(Component, Debug, Clone, PartialEq)]
+#[storage(HashMapStorage)]
+pub struct LocalTime {
+    factor: TimeFactor,
+}
+
+impl LocalTime {
+    pub fn new(factor: TimeFactor) -> LocalTime {
+        LocalTime { factor }
+    }
+
+    pub fn factor(&self) -> TimeFactor {
+        self.factor
+    }
+}
+
+impl Historical for LocalTime {}
+
+// Same as `LocalTime`, but for everyone else within `radius` tiles of the carrier.
+#[derive(Component, Debug, Clone, PartialEq)]
+#[storage(HashMapStorage)]
+pub struct TimeField {
+    factor: TimeFactor,
+    radius: i32,
+}
+
+impl TimeField {
+    pub fn new(factor: TimeFactor, radius: i32) -> TimeField {
+        TimeField { factor, radius }
+    }
+
+    pub fn factor(&self) -> TimeFactor {
+        self.factor
+    }
+
+    pub fn radius(&self) -> i32 {
+        self.radius
+    }
+}
+
+impl Historical for TimeField {}
+
+struct LocalTimeSystem;
+
+impl<'a> System<'a> for LocalTimeSystem {
+    type SystemData = (
+        Write<'a, Timekeeper>,
+        Read<'a, Dungeon>,
+        Entities<'a>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, LocalTime>,
+        ReadStorage<'a, TimeField>,
+    );
+
+    // Everyone on a level other than the active one is held in stasis until the player
+    // comes back.
+    fn run(
+        &mut self,
+        (mut time, dungeon, entity_s, position_s, local_s, field_s): Self::SystemData,
+    ) {
+        let mut factors = HashMap::new();
+        for (entity, local) in (&*entity_s, &local_s).join() {
+            factors.insert(entity, local.factor());
+        }
+        for (field_entity, field_position, field) in (&*entity_s, &position_s, &field_s).join() {
+            for (entity, position) in (&*entity_s, &position_s).join() {
+                if entity != field_entity
+                    && position.z() == field_position.z()
+                    && position.distance(field_position) <= field.radius()
+                {
+                    let factor = factors.entry(entity).or_insert_with(TimeFactor::default);
+                    *factor = *factor * field.factor();
+                }
+            }
+        }
+        for (entity, position) in (&*entity_s, &position_s).join() {
+            if position.z() != dungeon.active() {
+                factors.insert(entity, TimeFactor::new(0, 1));
+            }
+        }
+        time.set_local_factors(factors);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::physics::Direction;
+    use super::*;
+
+    #[test]
+    fn local_factors() {
+        let mut world = World::new();
+        world.register::<Position>();
+        world.register::<LocalTime>();
+        world.register::<TimeField>();
+        world.add_resource(Timekeeper::new());
+        world.add_resource(Dungeon::new());
+        let slowed = world
+            .create_entity()
+            .with(Position::new(0, 0, Direction::None))
+            .with(LocalTime::new(TimeFactor::new(1, 2)))
+            .build();
+        let hasted = world
+            .create_entity()
+            .with(Position::new(5, 5, Direction::None))
+            .with(TimeField::new(TimeFactor::new(2, 1), 1))
+            .build();
+        let bystander = world
+            .create_entity()
+            .with(Position::new(6, 4, Direction::None))
+            .build();
+        let frozen = world
+            .create_entity()
+            .with(Position::new(0, 1, Direction::None))
+            .with(LocalTime::new(TimeFactor::new(0, 1)))
+            .build();
+        let downstairs = world
+            .create_entity()
+            .with(Position::on_level(6, 5, 1, Direction::None))
+            .build();
+        LocalTimeSystem.run_now(&world.res);
+
+        let time = world.read_resource::<Timekeeper>();
+        let step = Duration::from_millis(250);
+        assert_eq!(time.local_factor(&hasted), TimeFactor::default());
+        assert_eq!(
+            time.to_global(&slowed, step),
+            Some(Duration::from_millis(500))
+        );
+        assert_eq!(
+            time.to_global(&bystander, step),
+            Some(Duration::from_millis(125))
+        );
+        assert_eq!(time.to_global(&frozen, step), None);
+        assert!(time.is_frozen(&frozen));
+        assert!(time.is_frozen(&downstairs));
+    }
+}
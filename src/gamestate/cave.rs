@@ -0,0 +1,332 @@
+use rand::prng::XorShiftRng;
+use rand::{Rng, SeedableRng};
+use std::cmp::{min, Reverse};
+use std::collections::VecDeque;
+
+use super::claims::Tile;
+use super::map::{TileMap, TileType};
+
+const NEIGHBOURS: [(i32, i32); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+const ORTHOGONAL: [(i32, i32); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+
+#[derive(Debug, Clone)]
+pub struct CaveParams {
+    pub width: i32,
+    pub height: i32,
+    // Chance, in percent, of a tile starting out as a wall.
+    pub fill_percent: u32,
+    pub smoothing_steps: u32,
+    pub items: usize,
+    pub monsters: usize,
+    pub ropes: usize,
+    pub holes: usize,
+    // Monsters aren't placed any closer than this to the up stairs.
+    pub safe_radius: i32,
+}
+
+impl Default for CaveParams {
+    fn default() -> Self {
+        CaveParams {
+            width: 64,
+            height: 48,
+            fill_percent: 45,
+            smoothing_steps: 5,
+            items: 8,
+            monsters: 6,
+            ropes: 1,
+            holes: 1,
+            safe_radius: 8,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Cave {
+    pub map: TileMap,
+    pub stairs_up: Tile,
+    pub stairs_down: Tile,
+    pub items: Vec<Tile>,
+    pub monsters: Vec<Tile>,
+}
+
+impl Cave {
+    // Same seed and parameters, same cave.
+    pub fn generate(seed: u64, params: &CaveParams) -> Cave {
+        let mut rng = XorShiftRng::from_seed(seed_bytes(seed));
+        let mut map = TileMap::new(params.width, params.height, TileType::Wall);
+        for y in 1..params.height - 1 {
+            for x in 1..params.width - 1 {
+                if rng.gen_range(0, 100) >= params.fill_percent {
+                    map.set((x, y), TileType::Floor);
+                }
+            }
+        }
+        for _ in 0..params.smoothing_steps {
+            map = smooth(&map);
+        }
+        join_regions(&mut map);
+
+        let mut floor = floor_tiles(&map);
+        if floor.is_empty() {
+            let center = (params.width / 2, params.height / 2);
+            map.set(center, TileType::Floor);
+            floor.push(center);
+        }
+        let stairs_up = floor[rng.gen_range(0, floor.len())];
+        let distances = distances(&map, stairs_up);
+        let stairs_down = floor
+            .iter()
+            .cloned()
+            .max_by_key(|&tile| distances[index(&map, tile)])
+            .unwrap_or(stairs_up);
+        map.set(stairs_up, TileType::StairsUp);
+        map.set(stairs_down, TileType::StairsDown);
+
+        let mut free = floor
+            .into_iter()
+            .filter(|&tile| tile != stairs_up && tile != stairs_down)
+            .collect::<Vec<_>>();
+        rng.shuffle(&mut free);
+        for _ in 0..params.ropes {
+            if let Some(tile) = free.pop() {
+                map.set(tile, TileType::Rope);
+            }
+        }
+        for _ in 0..params.holes {
+            if let Some(tile) = free.pop() {
+                map.set(tile, TileType::Hole);
+            }
+        }
+        let item_count = min(params.items, free.len());
+        let items = free.drain(..item_count).collect::<Vec<_>>();
+        let monsters = free
+            .into_iter()
+            .filter(|&(x, y)| {
+                (x - stairs_up.0).abs() + (y - stairs_up.1).abs() > params.safe_radius
+            })
+            .take(params.monsters)
+            .collect::<Vec<_>>();
+        info!(
+            "generated cave {}: {} items, {} monsters",
+            seed,
+            items.len(),
+            monsters.len()
+        );
+        Cave {
+            map,
+            stairs_up,
+            stairs_down,
+            items,
+            monsters,
+        }
+    }
+
+    pub fn to_ascii(&self) -> String {
+        let mut ascii = String::new();
+        for y in 0..self.map.height() {
+            for x in 0..self.map.width() {
+                let tile = (x, y);
+                ascii.push(if self.monsters.contains(&tile) {
+                    'm'
+                } else if self.items.contains(&tile) {
+                    '!'
+                } else {
+                    match self.map.get(tile) {
+                        TileType::Floor => '.',
+                        TileType::Wall => '#',
+                        TileType::Chasm => ' ',
+                        TileType::Water => '~',
+                        TileType::StairsUp => '<',
+                        TileType::StairsDown => '>',
+                        TileType::Rope => '^',
+                        TileType::Hole => 'o',
+                    }
+                });
+            }
+            ascii.push('\n');
+        }
+        ascii
+    }
+}
+
+fn seed_bytes(seed: u64) -> [u8; 16] {
+    let mut bytes = [0; 16];
+    for i in 0..8 {
+        bytes[i] = (seed >> (8 * i)) as u8;
+        // Keeps the seed from ever being all zeroes.
+        bytes[i + 8] = !bytes[i];
+    }
+    bytes
+}
+
+fn index(map: &TileMap, (x, y): Tile) -> usize {
+    (y * map.width() + x) as usize
+}
+
+fn floor_tiles(map: &TileMap) -> Vec<Tile> {
+    map.tiles()
+        .filter(|&(_, tile_type)| tile_type == TileType::Floor)
+        .map(|(tile, _)| tile)
+        .collect()
+}
+
+// Tiles with mostly walls around turn into walls, those with mostly floor into floor.
+fn smooth(map: &TileMap) -> TileMap {
+    let mut smoothed = map.clone();
+    for ((x, y), _) in map.tiles() {
+        if x == 0 || y == 0 || x == map.width() - 1 || y == map.height() - 1 {
+            continue;
+        }
+        let walls = NEIGHBOURS
+            .iter()
+            .filter(|&&(dx, dy)| map.get((x + dx, y + dy)) == TileType::Wall)
+            .count();
+        if walls > 4 {
+            smoothed.set((x, y), TileType::Wall);
+        } else if walls < 4 {
+            smoothed.set((x, y), TileType::Floor);
+        }
+    }
+    smoothed
+}
+
+fn regions(map: &TileMap) -> Vec<Vec<Tile>> {
+    let mut seen = vec![false; (map.width() * map.height()) as usize];
+    let mut regions = Vec::new();
+    for start in floor_tiles(map) {
+        if seen[index(map, start)] {
+            continue;
+        }
+        seen[index(map, start)] = true;
+        let mut region = Vec::new();
+        let mut queue = VecDeque::new();
+        queue.push_back(start);
+        while let Some((x, y)) = queue.pop_front() {
+            region.push((x, y));
+            for &(dx, dy) in &ORTHOGONAL {
+                let next = (x + dx, y + dy);
+                if map.get(next) == TileType::Floor && !seen[index(map, next)] {
+                    seen[index(map, next)] = true;
+                    queue.push_back(next);
+                }
+            }
+        }
+        regions.push(region);
+    }
+    regions
+}
+
+// Biggest region first, every other one gets the shortest tunnel to whatever is joined up
+// so far.
+fn join_regions(map: &mut TileMap) {
+    let mut regions = regions(map);
+    regions.sort_by_key(|region| Reverse(region.len()));
+    let mut joined = vec![false; (map.width() * map.height()) as usize];
+    if let Some(first) = regions.first() {
+        for &tile in first {
+            joined[index(map, tile)] = true;
+        }
+    }
+    for region in regions.iter().skip(1) {
+        if joined[index(map, region[0])] {
+            // Already cut through by an earlier tunnel.
+            for &tile in region {
+                joined[index(map, tile)] = true;
+            }
+            continue;
+        }
+        let mut parents = vec![None; joined.len()];
+        let mut queue = region.iter().cloned().collect::<VecDeque<_>>();
+        for &tile in region {
+            parents[index(map, tile)] = Some(tile);
+        }
+        let mut end = None;
+        while let Some((x, y)) = queue.pop_front() {
+            if joined[index(map, (x, y))] {
+                end = Some((x, y));
+                break;
+            }
+            for &(dx, dy) in &ORTHOGONAL {
+                let next = (x + dx, y + dy);
+                if next.0 < 1
+                    || next.1 < 1
+                    || next.0 >= map.width() - 1
+                    || next.1 >= map.height() - 1
+                {
+                    continue;
+                }
+                if parents[index(map, next)].is_none() {
+                    parents[index(map, next)] = Some((x, y));
+                    queue.push_back(next);
+                }
+            }
+        }
+        let mut tile = match end {
+            Some(end) => end,
+            None => continue,
+        };
+        while let Some(parent) = parents[index(map, tile)] {
+            map.set(tile, TileType::Floor);
+            joined[index(map, tile)] = true;
+            if parent == tile {
+                break;
+            }
+            tile = parent;
+        }
+        for &tile in region {
+            joined[index(map, tile)] = true;
+        }
+    }
+}
+
+// Walking distance from `from` to every tile, as long as there's a way.
+fn distances(map: &TileMap, from: Tile) -> Vec<Option<u32>> {
+    let mut distances = vec![None; (map.width() * map.height()) as usize];
+    distances[index(map, from)] = Some(0);
+    let mut queue = VecDeque::new();
+    queue.push_back(from);
+    while let Some((x, y)) = queue.pop_front() {
+        let distance = distances[index(map, (x, y))].unwrap_or(0);
+        for &(dx, dy) in &ORTHOGONAL {
+            let next = (x + dx, y + dy);
+            if map.get(next) != TileType::Wall && distances[index(map, next)].is_none() {
+                distances[index(map, next)] = Some(distance + 1);
+                queue.push_back(next);
+            }
+        }
+    }
+    distances
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cave_generation() {
+        let params = CaveParams::default();
+        let cave = Cave::generate(1234, &params);
+        assert_eq!(cave.to_ascii(), Cave::generate(1234, &params).to_ascii());
+        assert_ne!(cave.to_ascii(), Cave::generate(4321, &params).to_ascii());
+
+        assert_eq!(cave.map.get(cave.stairs_up), TileType::StairsUp);
+        assert_eq!(cave.map.get(cave.stairs_down), TileType::StairsDown);
+        assert_eq!(cave.items.len(), params.items);
+        assert_eq!(cave.monsters.len(), params.monsters);
+        let distances = distances(&cave.map, cave.stairs_up);
+        for (tile, tile_type) in cave.map.tiles() {
+            if tile_type != TileType::Wall {
+                assert!(distances[index(&cave.map, tile)].is_some(), "{:?}", tile);
+            }
+        }
+    }
+}
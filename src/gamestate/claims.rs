@@ -0,0 +1,336 @@
+use specs::prelude::*;
+use std::cmp::min;
+use std::collections::{BTreeMap, HashMap};
+
+use super::time::*;
+use super::timeline::{Moment, Timeline};
+
+pub type Tile = (i32, i32);
+// A tile on a particular dungeon level.
+pub type Cell = (i32, i32, i32);
+
+#[derive(Debug, Clone)]
+struct Claim {
+    entity: Entity,
+    start: Instant,
+    end: Instant,
+    scheduled: Moment,
+    // Whoever bumped this claim, and when; kept around so that rewinding or forking to
+    // before the bump brings the claim back.
+    superseded: Vec<(Entity, Moment)>,
+    // Where the claim was cut short, and when that was decided; same as bumps.
+    released: Vec<(Instant, Moment)>,
+}
+
+impl Claim {
+    fn is_live(&self, timeline: &Timeline) -> bool {
+        timeline.is_visible(self.scheduled)
+            && !self
+                .superseded
+                .iter()
+                .any(|&(_, moment)| timeline.is_visible(moment))
+    }
+
+    // Earliest release that happened on the current branch ends the claim early.
+    fn end(&self, timeline: &Timeline) -> Instant {
+        self.released
+            .iter()
+            .filter(|&&(_, moment)| timeline.is_visible(moment))
+            .map(|&(at, _)| at)
+            .fold(self.end, min)
+    }
+
+    fn overlaps(&self, timeline: &Timeline, other: &Claim) -> bool {
+        self.entity != other.entity
+            && self.start < other.end(timeline)
+            && other.start < self.end(timeline)
+    }
+
+    // Earlier start wins; entity index breaks ties, so the outcome doesn't depend on
+    // the order the claims came in.
+    fn precedes(&self, other: &Claim) -> bool {
+        (self.start, self.entity.id()) < (other.start, other.entity.id())
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ClaimOutcome {
+    Granted { bumped: Vec<Entity> },
+    Contested { winner: Entity },
+}
+
+#[derive(Clone)]
+pub struct TileClaims {
+    claims: BTreeMap<Cell, Vec<Claim>>,
+    pruned_until: PrunedUntil,
+}
+
+impl Default for TileClaims {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TileClaims {
+    pub fn new() -> TileClaims {
+        TileClaims {
+            claims: BTreeMap::new(),
+            pruned_until: PrunedUntil::default(),
+        }
+    }
+
+    // Same claims, held by whichever entities `entities` maps these ones to.
+    pub fn remapped(&self, entities: &HashMap<Entity, Entity>) -> TileClaims {
+        let remap = |claim: &Claim| {
+            let entity = *entities.get(&claim.entity)?;
+            let superseded = claim
+                .superseded
+                .iter()
+                .map(|&(by, moment)| (entities.get(&by).cloned().unwrap_or(by), moment))
+                .collect();
+            Some(Claim {
+                entity,
+                superseded,
+                ..claim.clone()
+            })
+        };
+        TileClaims {
+            claims: self
+                .claims
+                .iter()
+                .map(|(cell, claims)| (*cell, claims.iter().filter_map(&remap).collect()))
+                .collect(),
+            pruned_until: self.pruned_until,
+        }
+    }
+
+    pub fn prune(&mut self, limit: Instant) {
+        if !self.pruned_until.advance(limit) {
+            return;
+        }
+        let mut emptied = Vec::new();
+        for (cell, claims) in self.claims.iter_mut() {
+            claims.retain(|claim| claim.end >= limit);
+            if claims.is_empty() {
+                emptied.push(*cell);
+            }
+        }
+        for cell in emptied {
+            self.claims.remove(&cell);
+        }
+    }
+
+    pub fn claim(
+        &mut self,
+        timeline: &Timeline,
+        cell: Cell,
+        entity: Entity,
+        (start, end): (Instant, Instant),
+        scheduled: Moment,
+    ) -> ClaimOutcome {
+        let new = Claim {
+            entity,
+            start,
+            end,
+            scheduled,
+            superseded: Vec::new(),
+            released: Vec::new(),
+        };
+        let claims = self.claims.entry(cell).or_insert_with(Vec::new);
+        let winner = claims
+            .iter()
+            .filter(|claim| claim.is_live(timeline) && claim.overlaps(timeline, &new))
+            .filter(|claim| claim.precedes(&new))
+            .min_by_key(|claim| (claim.start, claim.entity.id()))
+            .map(|claim| claim.entity);
+        if let Some(winner) = winner {
+            info!("{:?} lost {:?} to {:?}", entity, cell, winner);
+            return ClaimOutcome::Contested { winner };
+        }
+        let mut bumped = Vec::new();
+        for claim in claims.iter_mut() {
+            if claim.is_live(timeline) && claim.overlaps(timeline, &new) {
+                info!("{:?} lost {:?} to {:?}", claim.entity, cell, entity);
+                claim.superseded.push((entity, scheduled));
+                bumped.push(claim.entity);
+            }
+        }
+        claims.push(new);
+        ClaimOutcome::Granted { bumped }
+    }
+
+    // Frees whatever `entity` was holding past `at`, as of `moment`.
+    pub fn release(&mut self, timeline: &Timeline, entity: Entity, at: Instant, moment: Moment) {
+        for claims in self.claims.values_mut() {
+            for claim in claims.iter_mut() {
+                if claim.entity == entity
+                    && claim.is_live(timeline)
+                    && claim.start <= at
+                    && at < claim.end(timeline)
+                {
+                    claim.released.push((at, moment));
+                }
+            }
+        }
+    }
+
+    pub fn holder(&self, timeline: &Timeline, cell: Cell, at: Instant) -> Option<Entity> {
+        self.claims.get(&cell).and_then(|claims| {
+            claims
+                .iter()
+                .find(|claim| {
+                    claim.is_live(timeline) && claim.start <= at && at < claim.end(timeline)
+                })
+                .map(|claim| claim.entity)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn claim_resolution() {
+        let mut world = World::new();
+        let first = world.create_entity().build();
+        let second = world.create_entity().build();
+        let third = world.create_entity().build();
+        let mut time = Timekeeper::new();
+        let timeline = Timeline::new();
+        let mut claims = TileClaims::new();
+        let moment = timeline.moment(&time);
+        let step = Duration::from_millis(250);
+
+        assert_eq!(
+            claims.claim(&timeline, (1, 1, 0), third, time.span(step), moment),
+            ClaimOutcome::Granted { bumped: vec![] }
+        );
+        assert_eq!(
+            claims.claim(&timeline, (1, 1, 0), first, time.span(step), moment),
+            ClaimOutcome::Granted {
+                bumped: vec![third]
+            }
+        );
+        assert_eq!(
+            claims.claim(&timeline, (1, 1, 0), second, time.span(step), moment),
+            ClaimOutcome::Contested { winner: first }
+        );
+        assert_eq!(
+            claims.claim(&timeline, (2, 1, 0), second, time.span(step), moment),
+            ClaimOutcome::Granted { bumped: vec![] }
+        );
+        assert_eq!(
+            claims.claim(&timeline, (1, 1, 1), third, time.span(step), moment),
+            ClaimOutcome::Granted { bumped: vec![] }
+        );
+        assert_eq!(claims.holder(&timeline, (1, 1, 0), time.now()), Some(first));
+
+        time.add_simulation_time(step);
+        time.update_real_time(step);
+        let moment = timeline.moment(&time);
+        assert_eq!(claims.holder(&timeline, (1, 1, 0), time.now()), None);
+        assert_eq!(
+            claims.claim(&timeline, (1, 1, 0), second, time.span(step), moment),
+            ClaimOutcome::Granted { bumped: vec![] }
+        );
+    }
+
+    #[test]
+    fn bumps_are_reversible() {
+        let mut world = World::new();
+        let first = world.create_entity().build();
+        let second = world.create_entity().build();
+        let third = world.create_entity().build();
+        let mut timeline = Timeline::new();
+        let mut claims = TileClaims::new();
+        let root = timeline.current();
+        let start = Instant::default();
+        let step = Duration::from_millis(250);
+
+        claims.claim(
+            &timeline,
+            (1, 1, 0),
+            third,
+            (start, start + step),
+            timeline.moment_at(start),
+        );
+        let bump = start + Duration::from_millis(100);
+        assert_eq!(
+            claims.claim(
+                &timeline,
+                (1, 1, 0),
+                first,
+                (start, start + step),
+                timeline.moment_at(bump)
+            ),
+            ClaimOutcome::Granted {
+                bumped: vec![third]
+            }
+        );
+        assert_eq!(claims.holder(&timeline, (1, 1, 0), start), Some(first));
+
+        // Neither the bump nor the claim that caused it happened on the new branch.
+        let fork = start + Duration::from_millis(50);
+        timeline.fork(fork);
+        assert_eq!(claims.holder(&timeline, (1, 1, 0), start), Some(third));
+        assert_eq!(
+            claims.claim(
+                &timeline,
+                (1, 1, 0),
+                second,
+                (fork, fork + step),
+                timeline.moment_at(fork)
+            ),
+            ClaimOutcome::Contested { winner: third }
+        );
+
+        assert!(timeline.switch_to(root));
+        assert_eq!(claims.holder(&timeline, (1, 1, 0), start), Some(first));
+
+        claims.prune(fork + step);
+        assert_eq!(claims.holder(&timeline, (1, 1, 0), start), None);
+        assert!(claims.claims.is_empty());
+    }
+
+    #[test]
+    fn releases_are_reversible() {
+        let mut world = World::new();
+        let first = world.create_entity().build();
+        let second = world.create_entity().build();
+        let mut timeline = Timeline::new();
+        let mut claims = TileClaims::new();
+        let root = timeline.current();
+        let start = Instant::default();
+        let step = Duration::from_millis(250);
+        let stop = start + Duration::from_millis(100);
+        let later = start + Duration::from_millis(150);
+
+        claims.claim(
+            &timeline,
+            (1, 1, 0),
+            first,
+            (start, start + step),
+            timeline.moment_at(start),
+        );
+        claims.release(&timeline, first, stop, timeline.moment_at(stop));
+        assert_eq!(claims.holder(&timeline, (1, 1, 0), later), None);
+
+        // The stop never happened on a branch that forked off before it.
+        timeline.fork(start + Duration::from_millis(50));
+        assert_eq!(claims.holder(&timeline, (1, 1, 0), later), Some(first));
+        assert_eq!(
+            claims.claim(
+                &timeline,
+                (1, 1, 0),
+                second,
+                (later, later + step),
+                timeline.moment_at(later)
+            ),
+            ClaimOutcome::Contested { winner: first }
+        );
+
+        assert!(timeline.switch_to(root));
+        assert_eq!(claims.holder(&timeline, (1, 1, 0), later), None);
+    }
+}
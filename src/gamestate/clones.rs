@@ -0,0 +1,234 @@
+use specs::prelude::*;
+
+use super::brains::{Actor, Executed};
+use super::command::GameCommand;
+use super::history::{Historical, Journal};
+use super::physics::*;
+use super::time::*;
+
+// Runs after arrivals, so a command recorded right as a move ended finds the clone where
+// the original was.
+pub fn module_systems<'a, 'b>(builder: DispatcherBuilder<'a, 'b>) -> DispatcherBuilder<'a, 'b> {
+    builder.with(CloneCommands, "clone_commands", &["movable_timing"])
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedCommand {
+    at: Instant,
+    command: GameCommand,
+    // Where the recorded entity stood when it issued the command, and what came of it.
+    position: Option<Position>,
+    executed: Executed,
+}
+
+#[derive(Debug, Clone)]
+pub struct Recording {
+    entity: Entity,
+    start: Instant,
+    origin: Position,
+    commands: Vec<RecordedCommand>,
+}
+
+impl Recording {
+    pub fn entity(&self) -> Entity {
+        self.entity
+    }
+
+    pub fn start(&self) -> Instant {
+        self.start
+    }
+
+    pub fn origin(&self) -> &Position {
+        &self.origin
+    }
+}
+
+pub struct CommandRecorder {
+    recording: Option<Recording>,
+    finished: Option<Recording>,
+}
+
+impl Default for CommandRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CommandRecorder {
+    pub fn new() -> CommandRecorder {
+        CommandRecorder {
+            recording: None,
+            finished: None,
+        }
+    }
+
+    pub fn start(&mut self, entity: Entity, at: Instant, origin: Position) {
+        info!("recording {:?} from {:?}", entity, at);
+        self.recording = Some(Recording {
+            entity,
+            start: at,
+            origin,
+            commands: Vec::new(),
+        });
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(recording) = self.recording.take() {
+            info!("recorded {} commands", recording.commands.len());
+            self.finished = Some(recording);
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    pub fn finished(&self) -> Option<&Recording> {
+        self.finished.as_ref()
+    }
+
+    pub fn record(
+        &mut self,
+        entity: Entity,
+        at: Instant,
+        command: GameCommand,
+        position: Option<Position>,
+        executed: Executed,
+    ) {
+        if let Some(ref mut recording) = self.recording {
+            if recording.entity == entity && recording.start <= at {
+                // Anything recorded after a rewind no longer happened.
+                recording.commands.retain(|recorded| recorded.at <= at);
+                recording.commands.push(RecordedCommand {
+                    at,
+                    command,
+                    position,
+                    executed,
+                });
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Paradox {
+    pub entity: Entity,
+    pub at: Instant,
+    pub expected: RecordedCommand,
+    pub position: Option<Position>,
+    pub executed: Executed,
+}
+
+pub struct Paradoxes {
+    paradoxes: Vec<Paradox>,
+}
+
+impl Default for Paradoxes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Paradoxes {
+    pub fn new() -> Paradoxes {
+        Paradoxes {
+            paradoxes: Vec::new(),
+        }
+    }
+
+    pub fn take(&mut self) -> Vec<Paradox> {
+        self.paradoxes.drain(..).collect()
+    }
+}
+
+// Replays a recording at the instants it was made.
+#[derive(Component, Debug, Clone)]
+#[storage(HashMapStorage)]
+pub struct CloneBrain {
+    commands: Vec<RecordedCommand>,
+    next: usize,
+}
+
+impl CloneBrain {
+    pub fn new(recording: &Recording) -> CloneBrain {
+        CloneBrain {
+            commands: recording.commands.clone(),
+            next: 0,
+        }
+    }
+
+    fn next_due(&mut self, now: Instant) -> Option<RecordedCommand> {
+        let recorded = self.commands.get(self.next)?;
+        if recorded.at > now {
+            return None;
+        }
+        self.next += 1;
+        Some(recorded.clone())
+    }
+
+    fn next_at(&self) -> Option<Instant> {
+        self.commands.get(self.next).map(|recorded| recorded.at)
+    }
+}
+
+impl Historical for CloneBrain {}
+
+struct CloneCommands;
+
+#[derive(SystemData)]
+struct CloneCommandsData<'a> {
+    actor: Actor<'a>,
+    paradoxes: Write<'a, Paradoxes>,
+    entity: Entities<'a>,
+    brain: WriteStorage<'a, CloneBrain>,
+    brain_journal: Write<'a, Journal<CloneBrain>>,
+    movable: WriteStorage<'a, Movable>,
+}
+
+impl<'a> System<'a> for CloneCommands {
+    type SystemData = CloneCommandsData<'a>;
+
+    // Replayed commands are journaled along with the brain's place in the recording, so
+    // rewinding and coming back replays them from history instead of issuing them again.
+    fn run(&mut self, mut data: Self::SystemData) {
+        if let DirectedTime::Past(_) = data.actor.time.delta() {
+            return;
+        }
+        let now = data.actor.time.now();
+        let moment = data.actor.timeline.moment(&data.actor.time);
+        let mut bumped = Vec::new();
+        for (entity, brain, movable) in (&*data.entity, &mut data.brain, &mut data.movable).join() {
+            let before = brain.clone();
+            while let Some(recorded) = brain.next_due(now) {
+                let position = data.actor.position.get(entity).cloned();
+                let executed = data
+                    .actor
+                    .execute(entity, movable, recorded.command, &mut bumped);
+                // Started after the timing system had its say about the horizon.
+                match executed {
+                    Executed::Moving(duration) | Executed::Bumped(duration) => {
+                        data.actor.time.limit_horizon(None, Some(now + duration))
+                    }
+                    _ => (),
+                }
+                if executed != recorded.executed || position != recorded.position {
+                    warn!("paradox: {:?} diverged from {:?}", entity, recorded);
+                    data.paradoxes.paradoxes.push(Paradox {
+                        entity,
+                        at: now,
+                        expected: recorded,
+                        position,
+                        executed,
+                    });
+                }
+            }
+            if brain.next != before.next {
+                data.brain_journal
+                    .record(moment, entity, Some(before), Some(brain.clone()));
+            }
+            // Stop right on the next command, rather than issuing it late.
+            data.actor.time.limit_horizon(None, brain.next_at());
+        }
+        data.actor.bump(bumped, &mut data.movable);
+    }
+}
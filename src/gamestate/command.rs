@@ -6,6 +6,7 @@ use super::physics::Direction;
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
 pub enum GameCommand {
     Move(Direction),
+    Stop,
 }
 
 pub struct GameCommandQueue {
@@ -32,4 +33,8 @@ impl GameCommandQueue {
     pub fn pop(&mut self) -> Option<GameCommand> {
         self.queue.pop_front()
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
 }
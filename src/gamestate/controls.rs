@@ -0,0 +1,176 @@
+use specs::prelude::*;
+use std::cmp::min;
+
+use super::energy::ChronoEnergy;
+use super::time::*;
+use super::timeline::Timeline;
+
+// How much the time factor may change per tick.
+const RAMP_NUMERATOR: i64 = 1;
+const RAMP_DENOMINATOR: u64 = 8;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum TimeControl {
+    Play,
+    FastForward,
+    Rewind,
+    StepBack,
+    StepForward,
+    Seek,
+    // Where a step or seek landed, until the player acts or picks another control.
+    Hold,
+}
+
+impl TimeControl {
+    fn factor(self) -> TimeFactor {
+        match self {
+            TimeControl::Play | TimeControl::StepForward => TimeFactor::new(1, 1),
+            TimeControl::FastForward => TimeFactor::new(4, 1),
+            TimeControl::Rewind => TimeFactor::new(-2, 1),
+            TimeControl::StepBack => TimeFactor::new(-1, 1),
+            TimeControl::Seek => TimeFactor::new(8, 1),
+            TimeControl::Hold => TimeFactor::new(0, 1),
+        }
+    }
+}
+
+pub struct TimeControls {
+    control: TimeControl,
+    // Factor to return to once back to plain play; `None` while the controls are idle.
+    base_factor: Option<TimeFactor>,
+    step_target: Instant,
+}
+
+impl Default for TimeControls {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TimeControls {
+    pub fn new() -> TimeControls {
+        TimeControls {
+            control: TimeControl::Play,
+            base_factor: None,
+            step_target: Instant::default(),
+        }
+    }
+
+    pub fn set(
+        &mut self,
+        control: TimeControl,
+        time: &Timekeeper,
+        timeline: &Timeline,
+        energy: &ChronoEnergy,
+    ) {
+        if control == TimeControl::Play && self.base_factor.is_none() {
+            return;
+        }
+        if energy.is_empty() && control.factor().numerator() < 0 {
+            info!("out of chrono energy, can't {:?}", control);
+            return;
+        }
+        if self.base_factor.is_none() {
+            self.base_factor = Some(time.exact_time_factor());
+        }
+        let (past, future) = time.horizon();
+        match control {
+            TimeControl::StepBack => self.step_target = past.unwrap_or_default(),
+            TimeControl::StepForward => {
+                let frontier = timeline.frontier();
+                self.step_target = min(future.unwrap_or(frontier), frontier);
+            }
+            _ => (),
+        }
+        info!("time control: {:?}", control);
+        self.control = control;
+    }
+
+    // Scrubs quickly to `target`, as far as it's already been simulated.
+    pub fn seek(
+        &mut self,
+        target: Instant,
+        time: &Timekeeper,
+        timeline: &Timeline,
+        energy: &ChronoEnergy,
+    ) {
+        if energy.is_empty() && target < time.now() {
+            info!("out of chrono energy, can't seek back");
+            return;
+        }
+        self.set(TimeControl::Seek, time, timeline, energy);
+        self.step_target = min(target, timeline.frontier());
+    }
+
+    // Ramps the factor towards what the control asks for, and scrubs through already
+    // simulated time without spending play budget.
+    // Running out of chrono energy drops back to play, and holds time still meanwhile.
+    fn apply(&mut self, time: &mut Timekeeper, timeline: &Timeline, energy: &ChronoEnergy) {
+        let base = match self.base_factor {
+            Some(base) => base,
+            None => return,
+        };
+        let now = time.now();
+        match self.control {
+            TimeControl::StepBack | TimeControl::StepForward | TimeControl::Seek
+                if now == self.step_target =>
+            {
+                // Arrived; stay put rather than carrying on past the target.
+                info!("time control: {:?}", TimeControl::Hold);
+                self.control = TimeControl::Hold;
+                time.set_exact_time_factor(TimeFactor::new(0, 1));
+            }
+            // Acting branches off here, and there's nothing left to replay.
+            TimeControl::Hold if now >= timeline.frontier() => {
+                info!("time control: {:?}", TimeControl::Play);
+                self.control = TimeControl::Play;
+            }
+            _ => (),
+        }
+        let rewinding = match self.control {
+            TimeControl::Rewind | TimeControl::StepBack => true,
+            TimeControl::Seek => self.step_target < now,
+            _ => false,
+        };
+        if rewinding && energy.is_empty() {
+            info!("out of chrono energy");
+            self.control = TimeControl::Play;
+        }
+        let target = match self.control {
+            TimeControl::Seek if self.step_target < now => {
+                base * self.control.factor() * TimeFactor::new(-1, 1)
+            }
+            control => base * control.factor(),
+        };
+        let ramped = time
+            .exact_time_factor()
+            .approach(target, TimeFactor::new(RAMP_NUMERATOR, RAMP_DENOMINATOR));
+        // Stays at zero instead, if rewinding is locked.
+        time.set_exact_time_factor(ramped);
+        let factor = time.exact_time_factor();
+        let scrub_target = match self.control {
+            TimeControl::Play | TimeControl::FastForward if now < timeline.frontier() => {
+                Some(timeline.frontier())
+            }
+            TimeControl::Play | TimeControl::FastForward | TimeControl::Hold => None,
+            TimeControl::Rewind => Some(time.rewind_limit()),
+            TimeControl::StepBack | TimeControl::StepForward | TimeControl::Seek => {
+                Some(self.step_target)
+            }
+        };
+        time.scrub_to(scrub_target);
+        if self.control == TimeControl::Play && factor == target && scrub_target.is_none() {
+            self.base_factor = None;
+        }
+    }
+}
+
+// Runs before time is advanced, outside of the dispatcher.
+pub fn run(world: &World) {
+    let mut time = world.write_resource::<Timekeeper>();
+    let timeline = world.read_resource::<Timeline>();
+    let energy = world.read_resource::<ChronoEnergy>();
+    world
+        .write_resource::<TimeControls>()
+        .apply(&mut time, &timeline, &energy);
+}
@@ -0,0 +1,212 @@
+use specs::prelude::*;
+
+use super::brains::PlayerBrain;
+use super::claims::Tile;
+use super::legs::Legs;
+use super::map::{TileMap, TileType};
+use super::physics::{Direction, Position};
+
+pub const DUNGEON_DEPTH: i32 = 5;
+// Large and odd, so neighbouring seeds don't end up sharing levels.
+const LEVEL_SEED_MIX: u64 = 0x9e37_79b9_7f4a_7c15;
+
+pub fn module_systems<'a, 'b>(builder: DispatcherBuilder<'a, 'b>) -> DispatcherBuilder<'a, 'b> {
+    builder.with(ActiveLevelSystem, "active_level", &[])
+}
+
+pub fn level_seed(seed: u64, z: i32) -> u64 {
+    seed ^ (z as u64).wrapping_mul(LEVEL_SEED_MIX)
+}
+
+#[derive(Debug, Clone)]
+pub struct Level {
+    pub map: TileMap,
+    pub stairs_up: Tile,
+    pub stairs_down: Tile,
+}
+
+// Levels stacked top to bottom; whichever one the player is on is active, the rest are
+// suspended.
+#[derive(Debug, Clone)]
+pub struct Dungeon {
+    levels: Vec<Level>,
+    active: i32,
+    // Bumped whenever a level is added or handed out for changing.
+    revision: u64,
+}
+
+impl Default for Dungeon {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Dungeon {
+    pub fn new() -> Dungeon {
+        Dungeon {
+            levels: Vec::new(),
+            active: 0,
+            revision: 0,
+        }
+    }
+
+    pub fn push(&mut self, level: Level) {
+        self.levels.push(level);
+        self.revision += 1;
+    }
+
+    pub fn level(&self, z: i32) -> Option<&Level> {
+        if z < 0 {
+            return None;
+        }
+        self.levels.get(z as usize)
+    }
+
+    pub fn level_mut(&mut self, z: i32) -> Option<&mut Level> {
+        if z < 0 {
+            return None;
+        }
+        self.revision += 1;
+        self.levels.get_mut(z as usize)
+    }
+
+    pub fn active(&self) -> i32 {
+        self.active
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    // Where moving `direction` from `position` ends up, if it can be done at all. Stairs
+    // lead to the matching stairs on the other level.
+    pub fn destination(
+        &self,
+        position: &Position,
+        direction: Direction,
+        legs: Legs,
+    ) -> Option<Position> {
+        let level = self.level(position.z())?;
+        let (x, y) = match direction {
+            Direction::D => {
+                let below = self.level(position.z() + 1)?;
+                match level.map.get(position.tile()) {
+                    TileType::StairsDown => below.stairs_up,
+                    TileType::Hole => below.map.nearest_passable(position.tile(), legs)?,
+                    _ => return None,
+                }
+            }
+            Direction::U => {
+                let above = self.level(position.z() - 1)?;
+                match level.map.get(position.tile()) {
+                    TileType::StairsUp => above.stairs_down,
+                    TileType::Rope => above.map.nearest_passable(position.tile(), legs)?,
+                    _ => return None,
+                }
+            }
+            _ => {
+                let target = position.moved(direction);
+                if !level.map.is_passable(target.tile(), legs) {
+                    return None;
+                }
+                return Some(target);
+            }
+        };
+        let z = match direction {
+            Direction::D => position.z() + 1,
+            _ => position.z() - 1,
+        };
+        Some(Position::on_level(x, y, z, direction))
+    }
+}
+
+struct ActiveLevelSystem;
+
+impl<'a> System<'a> for ActiveLevelSystem {
+    type SystemData = (
+        Write<'a, Dungeon>,
+        ReadStorage<'a, PlayerBrain>,
+        ReadStorage<'a, Position>,
+    );
+
+    fn run(&mut self, (mut dungeon, brain_s, position_s): Self::SystemData) {
+        if let Some((_, position)) = (&brain_s, &position_s).join().next() {
+            if dungeon.active != position.z() {
+                info!("level {} is now active", position.z());
+                dungeon.active = position.z();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn destinations() {
+        let mut dungeon = Dungeon::new();
+        for z in 0..2 {
+            let mut map = TileMap::new(8, 8, TileType::Floor);
+            map.set((1, 1), TileType::StairsUp);
+            map.set((6, 6), TileType::StairsDown);
+            map.set(
+                (3, 3),
+                if z == 0 {
+                    TileType::Hole
+                } else {
+                    TileType::Rope
+                },
+            );
+            map.set((4, 4), TileType::Wall);
+            dungeon.push(Level {
+                map,
+                stairs_up: (1, 1),
+                stairs_down: (6, 6),
+            });
+        }
+        let legs = Legs::Bipedal;
+        let destination = |x, y, z, direction| {
+            dungeon.destination(
+                &Position::on_level(x, y, z, Direction::None),
+                direction,
+                legs,
+            )
+        };
+        assert_eq!(destination(3, 4, 0, Direction::E), None);
+        assert_eq!(
+            destination(3, 4, 0, Direction::W),
+            Some(Position::on_level(2, 4, 0, Direction::W))
+        );
+        assert_eq!(destination(2, 2, 0, Direction::D), None);
+        assert_eq!(
+            destination(6, 6, 0, Direction::D),
+            Some(Position::on_level(1, 1, 1, Direction::D))
+        );
+        assert_eq!(destination(6, 6, 1, Direction::D), None);
+        assert_eq!(
+            destination(1, 1, 1, Direction::U),
+            Some(Position::on_level(6, 6, 0, Direction::U))
+        );
+        assert_eq!(destination(1, 1, 0, Direction::U), None);
+        assert_eq!(
+            destination(3, 3, 0, Direction::D),
+            Some(Position::on_level(3, 3, 1, Direction::D))
+        );
+        assert_eq!(
+            destination(3, 3, 1, Direction::U),
+            Some(Position::on_level(3, 3, 0, Direction::U))
+        );
+    }
+
+    #[test]
+    fn level_seeds() {
+        assert_eq!(level_seed(1234, 0), 1234);
+        for seed in 0..64 {
+            for z in 1..DUNGEON_DEPTH {
+                assert_ne!(level_seed(seed, z), level_seed(seed + 1, z - 1));
+                assert_ne!(level_seed(seed, z), level_seed(seed, z - 1));
+            }
+        }
+    }
+}
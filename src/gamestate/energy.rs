@@ -0,0 +1,136 @@
+use specs::prelude::*;
+use std::cmp::{max, min};
+
+use super::time::*;
+use super::timeline::Timeline;
+
+pub const BASE_CHRONO_ENERGY_SECS: u64 = 10;
+
+pub fn module_systems<'a, 'b>(builder: DispatcherBuilder<'a, 'b>) -> DispatcherBuilder<'a, 'b> {
+    builder.with(ChronoEnergySystem, "chrono_energy", &[])
+}
+
+// Rewinding spends it and simulating time that hasn't been simulated yet brings it back;
+// once it runs out, time can't be made to flow backwards.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChronoEnergy {
+    current: Duration,
+    capacity: Duration,
+    // Energy spent per second rewound, and regained per second played forward.
+    drain: TimeFactor,
+    regen: TimeFactor,
+}
+
+impl Default for ChronoEnergy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ChronoEnergy {
+    pub fn new() -> ChronoEnergy {
+        let capacity = Duration::from_secs(BASE_CHRONO_ENERGY_SECS);
+        ChronoEnergy {
+            current: capacity,
+            capacity,
+            drain: TimeFactor::new(1, 1),
+            regen: TimeFactor::new(1, 4),
+        }
+    }
+
+    pub fn current(&self) -> Duration {
+        self.current
+    }
+
+    pub fn capacity(&self) -> Duration {
+        self.capacity
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.current == Duration::from_secs(0)
+    }
+
+    pub fn fraction(&self) -> f32 {
+        if self.capacity == Duration::from_secs(0) {
+            return 0.0;
+        }
+        seconds(self.current) / seconds(self.capacity)
+    }
+
+    // What spells and items go through.
+    pub fn restore(&mut self, amount: Duration) {
+        self.current = min(self.current + amount, self.capacity);
+    }
+
+    pub fn spend(&mut self, amount: Duration) {
+        self.current = self
+            .current
+            .checked_sub(amount)
+            .unwrap_or_else(|| Duration::from_secs(0));
+    }
+
+    pub fn set_capacity(&mut self, capacity: Duration) {
+        self.capacity = capacity;
+        self.current = min(self.current, capacity);
+    }
+
+    fn update(&mut self, delta: DirectedTime) {
+        match delta {
+            DirectedTime::Past(delta) => {
+                let amount = self.drain.scale(delta);
+                self.spend(amount);
+            }
+            DirectedTime::Future(delta) => {
+                let amount = self.regen.scale(delta);
+                self.restore(amount);
+            }
+            DirectedTime::Still => (),
+        }
+    }
+}
+
+// Has to run before the timeline moves its frontier up to `now`.
+struct ChronoEnergySystem;
+
+impl<'a> System<'a> for ChronoEnergySystem {
+    type SystemData = (
+        Write<'a, Timekeeper>,
+        Read<'a, Timeline>,
+        Write<'a, ChronoEnergy>,
+    );
+
+    fn run(&mut self, (mut time, timeline, mut energy): Self::SystemData) {
+        // Replaying already simulated time doesn't pay anything back.
+        let delta = match time.delta() {
+            DirectedTime::Future(_) => {
+                DirectedTime::Future(time.now().since(max(time.previous(), timeline.frontier())))
+            }
+            delta => delta,
+        };
+        energy.update(delta);
+        time.lock_rewind(energy.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drain_and_regen() {
+        let mut energy = ChronoEnergy::new();
+        energy.update(DirectedTime::Past(Duration::from_secs(4)));
+        assert_eq!(energy.current(), Duration::from_secs(6));
+        energy.update(DirectedTime::Future(Duration::from_secs(4)));
+        assert_eq!(energy.current(), Duration::from_secs(7));
+        energy.update(DirectedTime::Past(Duration::from_secs(20)));
+        assert!(energy.is_empty());
+
+        energy.regen = TimeFactor::new(1, 1);
+        energy.update(DirectedTime::Future(Duration::from_secs(20)));
+        assert_eq!(energy.current(), energy.capacity());
+        energy.set_capacity(Duration::from_secs(5));
+        assert_eq!(energy.current(), Duration::from_secs(5));
+        assert_eq!(energy.fraction(), 1.0);
+    }
+}
@@ -0,0 +1,316 @@
+use specs::prelude::*;
+use std::collections::BTreeMap;
+use std::ops::Bound::{Excluded, Included, Unbounded};
+use std::sync::Arc;
+
+use super::time::*;
+use super::timeline::{BranchId, Moment, Timeline};
+
+// Something that happens at an instant: `fire` when time crosses it going forward,
+// `unfire` when it crosses back.
+pub trait TimedEvent: Send + Sync {
+    fn fire(&self, world: &mut World);
+    fn unfire(&self, world: &mut World);
+}
+
+struct FnEvent<F, U> {
+    fire: F,
+    unfire: U,
+}
+
+impl<F, U> TimedEvent for FnEvent<F, U>
+where
+    F: Fn(&mut World) + Send + Sync,
+    U: Fn(&mut World) + Send + Sync,
+{
+    fn fire(&self, world: &mut World) {
+        (self.fire)(world)
+    }
+
+    fn unfire(&self, world: &mut World) {
+        (self.unfire)(world)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct EventId(u64);
+
+#[derive(Clone)]
+struct ScheduledEvent {
+    scheduled: Moment,
+    event: Arc<TimedEvent>,
+}
+
+enum Crossing {
+    Forward(Arc<TimedEvent>),
+    Backward(Arc<TimedEvent>),
+}
+
+#[derive(Clone)]
+pub struct EventScheduler {
+    next_id: u64,
+    events: BTreeMap<Instant, Vec<ScheduledEvent>>,
+    pruned_until: PrunedUntil,
+}
+
+impl Default for EventScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventScheduler {
+    pub fn new() -> EventScheduler {
+        EventScheduler {
+            next_id: 0,
+            events: BTreeMap::new(),
+            pruned_until: PrunedUntil::default(),
+        }
+    }
+
+    pub fn schedule(&mut self, at: Instant, scheduled: Moment, event: Arc<TimedEvent>) -> EventId {
+        if at <= scheduled.instant() {
+            warn!("event scheduled at {:?} won't fire until replayed", at);
+        }
+        let id = EventId(self.next_id);
+        self.next_id += 1;
+        self.events
+            .entry(at)
+            .or_insert_with(Vec::new)
+            .push(ScheduledEvent { scheduled, event });
+        info!("scheduled event {:?} for {:?}", id, at);
+        id
+    }
+
+    pub fn schedule_fn<F, U>(
+        &mut self,
+        at: Instant,
+        scheduled: Moment,
+        fire: F,
+        unfire: U,
+    ) -> EventId
+    where
+        F: Fn(&mut World) + Send + Sync + 'static,
+        U: Fn(&mut World) + Send + Sync + 'static,
+    {
+        self.schedule(at, scheduled, Arc::new(FnEvent { fire, unfire }))
+    }
+
+    pub fn closest_future(&self, timeline: &Timeline, now: Instant) -> Option<Instant> {
+        self.events
+            .range((Excluded(now), Unbounded))
+            .find(|&(_, events)| is_any_visible(timeline, events))
+            .map(|(instant, _)| *instant)
+    }
+
+    pub fn closest_past(&self, timeline: &Timeline, now: Instant) -> Option<Instant> {
+        self.events
+            .range(..now)
+            .rev()
+            .find(|&(_, events)| is_any_visible(timeline, events))
+            .map(|(instant, _)| *instant)
+    }
+
+    fn prune(&mut self, limit: Instant) {
+        if self.pruned_until.advance(limit) {
+            self.pruned_until.split_off(&mut self.events);
+        }
+    }
+
+    fn crossings(&self, time: &Timekeeper, timeline: &Timeline) -> Vec<Crossing> {
+        let mut crossings = Vec::new();
+        let (previous, now) = (time.previous(), time.now());
+        if let Some(switched_from) = timeline.switched_from() {
+            self.reconcile(timeline, switched_from, previous, &mut crossings);
+        }
+        match time.delta() {
+            DirectedTime::Future(_) => {
+                for (_, events) in self.events.range((Excluded(previous), Included(now))) {
+                    for event in events {
+                        if timeline.is_visible(event.scheduled) {
+                            crossings.push(Crossing::Forward(event.event.clone()));
+                        }
+                    }
+                }
+            }
+            DirectedTime::Past(_) => {
+                for (_, events) in self.events.range((Excluded(now), Included(previous))).rev() {
+                    for event in events.iter().rev() {
+                        if timeline.is_visible(event.scheduled) {
+                            crossings.push(Crossing::Backward(event.event.clone()));
+                        }
+                    }
+                }
+            }
+            DirectedTime::Still => (),
+        }
+        crossings
+    }
+
+    fn reconcile(
+        &self,
+        timeline: &Timeline,
+        previous: BranchId,
+        at: Instant,
+        crossings: &mut Vec<Crossing>,
+    ) {
+        for (_, events) in self.events.range(..=at).rev() {
+            for event in events.iter().rev() {
+                if timeline.is_visible_from(previous, event.scheduled)
+                    && !timeline.is_visible(event.scheduled)
+                {
+                    crossings.push(Crossing::Backward(event.event.clone()));
+                }
+            }
+        }
+        for (_, events) in self.events.range(..=at) {
+            for event in events {
+                if timeline.is_visible(event.scheduled)
+                    && !timeline.is_visible_from(previous, event.scheduled)
+                {
+                    crossings.push(Crossing::Forward(event.event.clone()));
+                }
+            }
+        }
+    }
+}
+
+fn is_any_visible(timeline: &Timeline, events: &[ScheduledEvent]) -> bool {
+    events
+        .iter()
+        .any(|event| timeline.is_visible(event.scheduled))
+}
+
+// Events get the whole world, so they can't be run from inside the dispatcher.
+pub fn run(world: &mut World) {
+    let crossings = {
+        let time = world.read_resource::<Timekeeper>();
+        let timeline = world.read_resource::<Timeline>();
+        let scheduler = world.read_resource::<EventScheduler>();
+        scheduler.crossings(&time, &timeline)
+    };
+    for crossing in crossings {
+        match crossing {
+            Crossing::Forward(event) => event.fire(world),
+            Crossing::Backward(event) => event.unfire(world),
+        }
+    }
+    let mut time = world.write_resource::<Timekeeper>();
+    let timeline = world.read_resource::<Timeline>();
+    let mut scheduler = world.write_resource::<EventScheduler>();
+    scheduler.prune(time.rewind_limit());
+    let now = time.now();
+    time.limit_horizon(
+        scheduler.closest_past(&timeline, now),
+        scheduler.closest_future(&timeline, now),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Fuse {
+        burnt: bool,
+        fired: usize,
+    }
+
+    #[test]
+    fn fire_and_unfire() {
+        let mut world = World::new();
+        world.add_resource(Timekeeper::new());
+        world.add_resource(Timeline::new());
+        world.add_resource(EventScheduler::new());
+        world.add_resource(Fuse::default());
+        {
+            let time = world.read_resource::<Timekeeper>();
+            let timeline = world.read_resource::<Timeline>();
+            world.write_resource::<EventScheduler>().schedule_fn(
+                time.now() + Duration::from_secs(2),
+                timeline.moment(&time),
+                |world| {
+                    let mut fuse = world.write_resource::<Fuse>();
+                    fuse.burnt = true;
+                    fuse.fired += 1;
+                },
+                |world| world.write_resource::<Fuse>().burnt = false,
+            );
+        }
+        let step = |world: &mut World| {
+            world
+                .write_resource::<Timekeeper>()
+                .update_real_time(Duration::from_secs(3));
+            run(world);
+            world.read_resource::<Timekeeper>().delta()
+        };
+        run(&mut world);
+        world
+            .write_resource::<Timekeeper>()
+            .add_simulation_time(Duration::from_secs(3));
+
+        assert_eq!(
+            step(&mut world),
+            DirectedTime::Future(Duration::from_secs(2))
+        );
+        assert!(world.read_resource::<Fuse>().burnt);
+        assert_eq!(
+            step(&mut world),
+            DirectedTime::Future(Duration::from_secs(1))
+        );
+        assert!(world.read_resource::<Fuse>().burnt);
+
+        {
+            let mut time = world.write_resource::<Timekeeper>();
+            time.set_time_factor(-1.0);
+            time.add_simulation_time(Duration::from_secs(3));
+        }
+        assert_eq!(step(&mut world), DirectedTime::Past(Duration::from_secs(1)));
+        assert!(world.read_resource::<Fuse>().burnt);
+        assert_eq!(step(&mut world), DirectedTime::Past(Duration::from_secs(2)));
+        assert!(!world.read_resource::<Fuse>().burnt);
+        assert_eq!(world.read_resource::<Fuse>().fired, 1);
+    }
+
+    #[test]
+    fn pruning() {
+        let mut world = World::new();
+        let mut time = Timekeeper::new();
+        time.set_history_horizon(Some(Duration::from_secs(1)));
+        time.add_simulation_time(Duration::from_secs(3));
+        world.add_resource(time);
+        world.add_resource(Timeline::new());
+        world.add_resource(EventScheduler::new());
+        {
+            let time = world.read_resource::<Timekeeper>();
+            let timeline = world.read_resource::<Timeline>();
+            let mut scheduler = world.write_resource::<EventScheduler>();
+            for secs in 1..4 {
+                scheduler.schedule_fn(
+                    time.now() + Duration::from_secs(secs),
+                    timeline.moment(&time),
+                    |_| (),
+                    |_| (),
+                );
+            }
+        }
+        for _ in 0..3 {
+            world
+                .write_resource::<Timekeeper>()
+                .update_real_time(Duration::from_secs(1));
+            run(&mut world);
+        }
+        assert_eq!(
+            world
+                .read_resource::<EventScheduler>()
+                .events
+                .keys()
+                .cloned()
+                .collect::<Vec<_>>(),
+            vec![
+                Instant::default() + Duration::from_secs(2),
+                Instant::default() + Duration::from_secs(3)
+            ]
+        );
+    }
+}
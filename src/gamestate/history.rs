@@ -0,0 +1,634 @@
+use specs::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use std::marker::PhantomData;
+use std::ops::Bound::{Excluded, Included};
+
+use super::brains::PlayerBrain;
+use super::bubbles::{LocalTime, TimeField};
+use super::clones::CloneBrain;
+use super::legs::Legs;
+use super::physics::*;
+use super::stats::{Equipment, Stats};
+use super::time::*;
+use super::timeline::{BranchId, Moment, Timeline};
+use super::visual::BaseSprite;
+
+pub fn module_systems<'a, 'b>(builder: DispatcherBuilder<'a, 'b>) -> DispatcherBuilder<'a, 'b> {
+    builder
+        .with(JournalSystem::<Position>::new(), "position_journal", &[])
+        .with(JournalSystem::<Movable>::new(), "movable_journal", &[])
+        .with(JournalSystem::<LocalTime>::new(), "local_time_journal", &[])
+        .with(JournalSystem::<TimeField>::new(), "time_field_journal", &[])
+        .with(JournalSystem::<Stats>::new(), "stats_journal", &[])
+        .with(JournalSystem::<Equipment>::new(), "equipment_journal", &[])
+        .with(JournalSystem::<Legs>::new(), "legs_journal", &[])
+        .with(JournalSystem::<BaseSprite>::new(), "sprite_journal", &[])
+        .with(
+            JournalSystem::<PlayerBrain>::new(),
+            "player_brain_journal",
+            &[],
+        )
+        .with(
+            JournalSystem::<CloneBrain>::new(),
+            "clone_brain_journal",
+            &[],
+        )
+        // World has to be rewound to `now` before anything else looks at it.
+        .with_barrier()
+}
+
+pub trait Historical: Component + Clone {}
+
+#[derive(Debug, Clone)]
+struct Change<T> {
+    moment: Moment,
+    entity: Entity,
+    before: Option<T>,
+    after: Option<T>,
+}
+
+pub struct Journal<T> {
+    changes: BTreeMap<Instant, Vec<Change<T>>>,
+    pruned_until: PrunedUntil,
+}
+
+impl<T> Default for Journal<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Journal<T> {
+    fn new() -> Journal<T> {
+        Journal {
+            changes: BTreeMap::new(),
+            pruned_until: PrunedUntil::default(),
+        }
+    }
+
+    pub fn record(&mut self, at: Moment, entity: Entity, before: Option<T>, after: Option<T>) {
+        self.changes
+            .entry(at.instant())
+            .or_insert_with(Vec::new)
+            .push(Change {
+                moment: at,
+                entity,
+                before,
+                after,
+            });
+    }
+
+    // Whether `entity` already has a visible change at `at` that `matches` its before and
+    // after values, e.g. from being replayed.
+    pub fn has_change_where<F>(
+        &self,
+        timeline: &Timeline,
+        entity: Entity,
+        at: Instant,
+        matches: F,
+    ) -> bool
+    where
+        F: Fn(Option<&T>, Option<&T>) -> bool,
+    {
+        self.changes.get(&at).map_or(false, |changes| {
+            changes.iter().any(|change| {
+                change.entity == entity
+                    && timeline.is_visible(change.moment)
+                    && matches(change.before.as_ref(), change.after.as_ref())
+            })
+        })
+    }
+
+    fn prune(&mut self, limit: Instant) {
+        if self.pruned_until.advance(limit) {
+            self.pruned_until.split_off(&mut self.changes);
+        }
+    }
+
+    pub fn latest(&self) -> Option<Instant> {
+        self.changes.keys().next_back().cloned()
+    }
+
+    // Values set at visible instants in `(from, to]`, earliest first.
+    pub fn recorded(
+        &self,
+        timeline: &Timeline,
+        from: Instant,
+        to: Instant,
+    ) -> Vec<(Instant, Entity, &T)> {
+        let mut recorded = Vec::new();
+        for (instant, changes) in self.changes.range((Excluded(from), Included(to))) {
+            for change in changes {
+                if let Some(ref after) = change.after {
+                    if timeline.is_visible(change.moment) {
+                        recorded.push((*instant, change.entity, after));
+                    }
+                }
+            }
+        }
+        recorded
+    }
+}
+
+impl<T> Journal<T>
+where
+    T: Historical,
+{
+    pub fn modify<F>(
+        &mut self,
+        at: Moment,
+        entity: Entity,
+        storage: &mut WriteStorage<T>,
+        modifier: F,
+    ) where
+        F: FnOnce(&mut T),
+    {
+        if let Some(component) = storage.get_mut(entity) {
+            let before = component.clone();
+            modifier(component);
+            self.record(at, entity, Some(before), Some(component.clone()));
+        }
+    }
+
+    pub fn insert(&mut self, at: Moment, entity: Entity, storage: &mut WriteStorage<T>, value: T) {
+        let before = storage.get(entity).cloned();
+        if apply(storage, entity, Some(&value)) {
+            self.record(at, entity, before, Some(value));
+        }
+    }
+
+    pub fn remove(&mut self, at: Moment, entity: Entity, storage: &mut WriteStorage<T>) {
+        if let Some(before) = storage.remove(entity) {
+            self.record(at, entity, Some(before), None);
+        }
+    }
+
+    // Reverts visible changes in `(from, to]`, latest first.
+    fn undo(&self, timeline: &Timeline, from: Instant, to: Instant, storage: &mut WriteStorage<T>) {
+        for (instant, changes) in self.changes.range((Excluded(from), Included(to))).rev() {
+            for change in changes.iter().rev() {
+                if timeline.is_visible(change.moment) {
+                    apply(storage, change.entity, change.before.as_ref());
+                }
+            }
+        }
+    }
+
+    // Reapplies visible changes in `(from, to]`, earliest first.
+    fn redo(&self, timeline: &Timeline, from: Instant, to: Instant, storage: &mut WriteStorage<T>) {
+        for (instant, changes) in self.changes.range((Excluded(from), Included(to))) {
+            for change in changes {
+                if timeline.is_visible(change.moment) {
+                    apply(storage, change.entity, change.after.as_ref());
+                }
+            }
+        }
+    }
+
+    // Turns state at `at` as seen from `previous` branch into state as seen from the current one.
+    fn reconcile(
+        &self,
+        timeline: &Timeline,
+        previous: BranchId,
+        at: Instant,
+        storage: &mut WriteStorage<T>,
+    ) {
+        for (instant, changes) in self.changes.range(..=at).rev() {
+            for change in changes.iter().rev() {
+                let moment = change.moment;
+                if timeline.is_visible_from(previous, moment) && !timeline.is_visible(moment) {
+                    apply(storage, change.entity, change.before.as_ref());
+                }
+            }
+        }
+        for (instant, changes) in self.changes.range(..=at) {
+            for change in changes {
+                let moment = change.moment;
+                if timeline.is_visible(moment) && !timeline.is_visible_from(previous, moment) {
+                    apply(storage, change.entity, change.after.as_ref());
+                }
+            }
+        }
+    }
+}
+
+fn apply<T>(storage: &mut WriteStorage<T>, entity: Entity, value: Option<&T>) -> bool
+where
+    T: Historical,
+{
+    match value {
+        Some(value) => match storage.insert(entity, value.clone()) {
+            Ok(_) => true,
+            Err(error) => {
+                warn!("can't restore {:?}: {:?}", entity, error);
+                false
+            }
+        },
+        None => {
+            storage.remove(entity);
+            true
+        }
+    }
+}
+
+type Remover = fn(&Resources, Moment, Entity);
+type Copier = fn(&World, &World, &HashMap<Entity, Entity>);
+
+// Every journaled component type, so an entity can be despawned or copied without knowing
+// what it has.
+pub struct Journals {
+    removers: Vec<Remover>,
+    copiers: Vec<Copier>,
+}
+
+impl Default for Journals {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Journals {
+    pub fn new() -> Journals {
+        Journals {
+            removers: Vec::new(),
+            copiers: Vec::new(),
+        }
+    }
+
+    fn register<T>(&mut self)
+    where
+        T: Historical + Send + Sync,
+    {
+        self.removers.push(remove_recorded::<T>);
+        self.copiers.push(copy_components::<T>);
+    }
+
+    // Copies components over to whichever entities `entities` maps their owners to; the
+    // copies start out with empty journals.
+    pub fn copy(&self, from: &World, to: &World, entities: &HashMap<Entity, Entity>) {
+        for copier in &self.copiers {
+            copier(from, to, entities);
+        }
+    }
+}
+
+fn copy_components<T>(from: &World, to: &World, entities: &HashMap<Entity, Entity>)
+where
+    T: Historical + Send + Sync,
+{
+    let from_storage = from.read_storage::<T>();
+    let mut to_storage = to.write_storage::<T>();
+    for (entity, component) in (&*from.entities(), &from_storage).join() {
+        if let Some(&mapped) = entities.get(&entity) {
+            apply(&mut to_storage, mapped, Some(component));
+        }
+    }
+    *to.write_resource::<Journal<T>>() = Journal::new();
+}
+
+fn remove_recorded<T>(resources: &Resources, at: Moment, entity: Entity)
+where
+    T: Historical + Send + Sync,
+{
+    let mut storage: WriteStorage<T> = SystemData::fetch(resources);
+    resources
+        .fetch_mut::<Journal<T>>()
+        .remove(at, entity, &mut storage);
+}
+
+// Entities are never deleted; spawning and despawning only journal their components in and
+// out, so rewinding brings them back exactly as they were.
+pub struct Spawn<'a> {
+    world: &'a World,
+    entity: Entity,
+    moment: Moment,
+}
+
+impl<'a> Spawn<'a> {
+    pub fn with<T>(self, component: T) -> Spawn<'a>
+    where
+        T: Historical + Send + Sync,
+    {
+        {
+            let mut storage = self.world.write_storage::<T>();
+            self.world.write_resource::<Journal<T>>().insert(
+                self.moment,
+                self.entity,
+                &mut storage,
+                component,
+            );
+        }
+        self
+    }
+
+    pub fn build(self) -> Entity {
+        info!("spawned {:?} at {:?}", self.entity, self.moment);
+        self.entity
+    }
+}
+
+pub fn spawn(world: &World) -> Spawn {
+    let moment = {
+        let time = world.read_resource::<Timekeeper>();
+        world.read_resource::<Timeline>().moment(&time)
+    };
+    Spawn {
+        world,
+        entity: world.entities().create(),
+        moment,
+    }
+}
+
+pub fn despawn(world: &World, entity: Entity) {
+    let moment = {
+        let time = world.read_resource::<Timekeeper>();
+        world.read_resource::<Timeline>().moment(&time)
+    };
+    for remover in &world.read_resource::<Journals>().removers {
+        remover(&world.res, moment, entity);
+    }
+    info!("despawned {:?} at {:?}", entity, moment);
+}
+
+pub struct JournalSystem<T> {
+    phantom_data: PhantomData<T>,
+}
+
+impl<T> JournalSystem<T> {
+    pub fn new() -> JournalSystem<T> {
+        JournalSystem {
+            phantom_data: PhantomData,
+        }
+    }
+}
+
+impl<'a, T> System<'a> for JournalSystem<T>
+where
+    T: Historical + Send + Sync,
+{
+    type SystemData = (
+        Read<'a, Timekeeper>,
+        Read<'a, Timeline>,
+        WriteStorage<'a, T>,
+        Write<'a, Journal<T>>,
+    );
+
+    fn run(&mut self, (time, timeline, mut storage, mut journal): Self::SystemData) {
+        let (previous, now) = (time.previous(), time.now());
+        if let Some(switched_from) = timeline.switched_from() {
+            journal.reconcile(&timeline, switched_from, previous, &mut storage);
+        }
+        match time.delta() {
+            DirectedTime::Past(_) => journal.undo(&timeline, now, previous, &mut storage),
+            DirectedTime::Future(_) => journal.redo(&timeline, previous, now, &mut storage),
+            DirectedTime::Still => (),
+        }
+        journal.prune(time.rewind_limit());
+    }
+
+    fn setup(&mut self, resources: &mut Resources) {
+        Self::SystemData::setup(resources);
+        resources.insert(Journal::<T>::new());
+        <Write<Journals> as SystemData>::setup(resources);
+        resources.fetch_mut::<Journals>().register::<T>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undo_redo() {
+        let mut world = World::new();
+        world.register::<Position>();
+        let entity = world
+            .create_entity()
+            .with(Position::new(0, 0, Direction::None))
+            .build();
+        let mut time = Timekeeper::new();
+        let timeline = Timeline::new();
+        let mut journal = Journal::<Position>::new();
+        let start = time.now();
+        time.add_simulation_time(Duration::from_secs(8));
+        for x in 1..5 {
+            time.update_real_time(Duration::from_secs(1));
+            journal.modify(
+                timeline.moment(&time),
+                entity,
+                &mut world.write_storage::<Position>(),
+                |position| *position = Position::new(x, 0, Direction::E),
+            );
+        }
+        let end = time.now();
+        assert_eq!(journal.latest(), Some(end));
+        let moved_to = |x| {
+            move |_: Option<&Position>, after: Option<&Position>| {
+                after.map_or(false, |position| position.x() == x)
+            }
+        };
+        assert!(journal.has_change_where(&timeline, entity, end, moved_to(4)));
+        assert!(!journal.has_change_where(&timeline, entity, end, moved_to(3)));
+        assert_eq!(
+            journal
+                .recorded(&timeline, start + Duration::from_secs(2), end)
+                .iter()
+                .map(|&(_, _, position)| position.x())
+                .collect::<Vec<_>>(),
+            vec![3, 4]
+        );
+
+        journal.undo(
+            &timeline,
+            start + Duration::from_secs(2),
+            end,
+            &mut world.write_storage(),
+        );
+        assert_eq!(
+            world.read_storage::<Position>().get(entity),
+            Some(&Position::new(2, 0, Direction::E))
+        );
+        journal.undo(
+            &timeline,
+            start,
+            start + Duration::from_secs(2),
+            &mut world.write_storage(),
+        );
+        assert_eq!(
+            world.read_storage::<Position>().get(entity),
+            Some(&Position::new(0, 0, Direction::None))
+        );
+        journal.redo(
+            &timeline,
+            start,
+            start + Duration::from_secs(3),
+            &mut world.write_storage(),
+        );
+        assert_eq!(
+            world.read_storage::<Position>().get(entity),
+            Some(&Position::new(3, 0, Direction::E))
+        );
+    }
+
+    #[test]
+    fn journal_pruning() {
+        let mut world = World::new();
+        world.register::<Position>();
+        let entity = world
+            .create_entity()
+            .with(Position::new(0, 0, Direction::None))
+            .build();
+        let mut time = Timekeeper::new();
+        let timeline = Timeline::new();
+        let mut journal = Journal::<Position>::new();
+        let step = Duration::from_millis(1);
+        time.set_history_horizon(Some(Duration::from_secs(1)));
+        for x in 0..10_000 {
+            time.add_simulation_time(step);
+            time.update_real_time(step);
+            journal.modify(
+                timeline.moment(&time),
+                entity,
+                &mut world.write_storage::<Position>(),
+                |position| *position = Position::new(x, 0, Direction::E),
+            );
+            journal.prune(time.rewind_limit());
+            assert!(journal.changes.len() <= 1_001);
+        }
+        assert_eq!(journal.latest(), Some(time.now()));
+    }
+
+    #[test]
+    fn undo_insert_remove() {
+        let mut world = World::new();
+        world.register::<Position>();
+        let entity = world.create_entity().build();
+        let mut time = Timekeeper::new();
+        let timeline = Timeline::new();
+        let mut journal = Journal::<Position>::new();
+        let start = time.now();
+        time.add_simulation_time(Duration::from_secs(2));
+        time.update_real_time(Duration::from_secs(1));
+        journal.insert(
+            timeline.moment(&time),
+            entity,
+            &mut world.write_storage(),
+            Position::new(1, 1, Direction::None),
+        );
+        time.update_real_time(Duration::from_secs(1));
+        journal.remove(timeline.moment(&time), entity, &mut world.write_storage());
+        assert_eq!(world.read_storage::<Position>().get(entity), None);
+
+        journal.undo(
+            &timeline,
+            start + Duration::from_secs(1),
+            time.now(),
+            &mut world.write_storage(),
+        );
+        assert_eq!(
+            world.read_storage::<Position>().get(entity),
+            Some(&Position::new(1, 1, Direction::None))
+        );
+        journal.undo(&timeline, start, time.now(), &mut world.write_storage());
+        assert_eq!(world.read_storage::<Position>().get(entity), None);
+    }
+
+    #[test]
+    fn spawn_despawn() {
+        let mut world = World::new();
+        world.add_resource(Timekeeper::new());
+        world.add_resource(Timeline::new());
+        let mut system = JournalSystem::<Position>::new();
+        System::setup(&mut system, &mut world.res);
+        let mut step = |world: &mut World, factor: f32, millis: u64| {
+            {
+                let mut time = world.write_resource::<Timekeeper>();
+                time.set_time_factor(factor);
+                time.add_simulation_time(Duration::from_millis(millis));
+                time.update_real_time(Duration::from_millis(millis));
+            }
+            system.run_now(&world.res);
+        };
+
+        step(&mut world, 1.0, 1000);
+        let entity = spawn(&world)
+            .with(Position::new(1, 1, Direction::None))
+            .build();
+        step(&mut world, 1.0, 1000);
+        despawn(&world, entity);
+        assert_eq!(world.read_storage::<Position>().get(entity), None);
+
+        step(&mut world, -1.0, 500);
+        assert_eq!(
+            world.read_storage::<Position>().get(entity),
+            Some(&Position::new(1, 1, Direction::None))
+        );
+        step(&mut world, -1.0, 1000);
+        assert_eq!(world.read_storage::<Position>().get(entity), None);
+        step(&mut world, 1.0, 1000);
+        assert_eq!(
+            world.read_storage::<Position>().get(entity),
+            Some(&Position::new(1, 1, Direction::None))
+        );
+        step(&mut world, 1.0, 1000);
+        assert_eq!(world.read_storage::<Position>().get(entity), None);
+    }
+
+    #[test]
+    fn branch_switching() {
+        let mut world = World::new();
+        world.register::<Position>();
+        let entity = world
+            .create_entity()
+            .with(Position::new(0, 0, Direction::None))
+            .build();
+        let mut time = Timekeeper::new();
+        let mut timeline = Timeline::new();
+        let mut journal = Journal::<Position>::new();
+        let root = timeline.current();
+        time.add_simulation_time(Duration::from_secs(4));
+        for x in 1..5 {
+            time.update_real_time(Duration::from_secs(1));
+            journal.modify(
+                timeline.moment(&time),
+                entity,
+                &mut world.write_storage::<Position>(),
+                |position| *position = Position::new(x, 0, Direction::E),
+            );
+        }
+        let end = time.now();
+        time.set_time_factor(-1.0);
+        time.add_simulation_time(Duration::from_secs(2));
+        time.update_real_time(Duration::from_secs(2));
+        journal.undo(&timeline, time.now(), end, &mut world.write_storage());
+        let fork = time.now();
+        let child = timeline.fork(fork);
+        time.set_time_factor(1.0);
+        time.add_simulation_time(Duration::from_secs(1));
+        time.update_real_time(Duration::from_secs(1));
+        journal.modify(
+            timeline.moment(&time),
+            entity,
+            &mut world.write_storage::<Position>(),
+            |position| *position = Position::new(10, 0, Direction::E),
+        );
+
+        timeline.switch_to(root);
+        journal.reconcile(&timeline, child, time.now(), &mut world.write_storage());
+        assert_eq!(
+            world.read_storage::<Position>().get(entity),
+            Some(&Position::new(3, 0, Direction::E))
+        );
+        timeline.switch_to(child);
+        journal.reconcile(&timeline, root, time.now(), &mut world.write_storage());
+        assert_eq!(
+            world.read_storage::<Position>().get(entity),
+            Some(&Position::new(10, 0, Direction::E))
+        );
+        journal.undo(&timeline, fork, time.now(), &mut world.write_storage());
+        assert_eq!(
+            world.read_storage::<Position>().get(entity),
+            Some(&Position::new(2, 0, Direction::E))
+        );
+    }
+}
@@ -1,8 +1,31 @@
 use specs::prelude::*;
-use std::marker::PhantomData;
 
-use super::physics::Direction;
-use super::command::{GameCommand, GameCommandQueue};
+use super::history::Historical;
+use super::time::TimeFactor;
 
-#[derive(Component)]
-pub struct Legs;
\ No newline at end of file
+#[derive(Component, Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Legs {
+    Bipedal,
+    Quadrupedal,
+    Slithering,
+    Winged,
+}
+
+impl Default for Legs {
+    fn default() -> Self {
+        Legs::Bipedal
+    }
+}
+
+impl Legs {
+    pub fn move_factor(self) -> TimeFactor {
+        match self {
+            Legs::Bipedal => TimeFactor::new(1, 1),
+            Legs::Quadrupedal => TimeFactor::new(4, 5),
+            Legs::Slithering => TimeFactor::new(5, 4),
+            Legs::Winged => TimeFactor::new(3, 4),
+        }
+    }
+}
+
+impl Historical for Legs {}
@@ -0,0 +1,160 @@
+use std::cmp::max;
+
+use super::claims::Tile;
+use super::legs::Legs;
+use super::time::TimeFactor;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum TileType {
+    Floor,
+    Wall,
+    Chasm,
+    Water,
+    StairsUp,
+    StairsDown,
+    // Climb up a rope, drop down a hole; either way, wherever's closest on the other level.
+    Rope,
+    Hole,
+}
+
+impl Default for TileType {
+    fn default() -> Self {
+        TileType::Floor
+    }
+}
+
+impl TileType {
+    pub fn is_passable(self, legs: Legs) -> bool {
+        match self {
+            TileType::Floor
+            | TileType::StairsUp
+            | TileType::StairsDown
+            | TileType::Rope
+            | TileType::Hole => true,
+            TileType::Wall => false,
+            TileType::Chasm => legs == Legs::Winged,
+            TileType::Water => legs == Legs::Winged || legs == Legs::Slithering,
+        }
+    }
+
+    // How much longer than plain floor it takes to step onto the tile.
+    pub fn move_factor(self, legs: Legs) -> TimeFactor {
+        match self {
+            TileType::Water if legs != Legs::Winged => TimeFactor::new(2, 1),
+            _ => TimeFactor::new(1, 1),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TileMap {
+    width: i32,
+    height: i32,
+    tiles: Vec<TileType>,
+}
+
+impl Default for TileMap {
+    fn default() -> Self {
+        Self::new(0, 0, TileType::Floor)
+    }
+}
+
+impl TileMap {
+    pub fn new(width: i32, height: i32, fill: TileType) -> TileMap {
+        TileMap {
+            width,
+            height,
+            tiles: vec![fill; (width * height) as usize],
+        }
+    }
+
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    fn index(&self, (x, y): Tile) -> Option<usize> {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return None;
+        }
+        Some((y * self.width + x) as usize)
+    }
+
+    // Anything off the map is solid rock.
+    pub fn get(&self, tile: Tile) -> TileType {
+        self.index(tile)
+            .map_or(TileType::Wall, |index| self.tiles[index])
+    }
+
+    pub fn set(&mut self, tile: Tile, tile_type: TileType) {
+        if let Some(index) = self.index(tile) {
+            self.tiles[index] = tile_type;
+        }
+    }
+
+    #[cfg(test)]
+    pub fn fill(&mut self, (x0, y0): Tile, (x1, y1): Tile, tile_type: TileType) {
+        for y in y0..=y1 {
+            for x in x0..=x1 {
+                self.set((x, y), tile_type);
+            }
+        }
+    }
+
+    pub fn is_passable(&self, tile: Tile, legs: Legs) -> bool {
+        self.get(tile).is_passable(legs)
+    }
+
+    // Closest tile to `tile` that can be stood on, searching in widening squares.
+    pub fn nearest_passable(&self, (x, y): Tile, legs: Legs) -> Option<Tile> {
+        for radius in 0..max(self.width, self.height) {
+            for dy in -radius..=radius {
+                for dx in -radius..=radius {
+                    if max(dx.abs(), dy.abs()) == radius && self.is_passable((x + dx, y + dy), legs)
+                    {
+                        return Some((x + dx, y + dy));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    pub fn tiles<'a>(&'a self) -> impl Iterator<Item = (Tile, TileType)> + 'a {
+        let width = self.width;
+        self.tiles
+            .iter()
+            .enumerate()
+            .map(move |(index, &tile_type)| {
+                let index = index as i32;
+                ((index % width, index / width), tile_type)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn passability() {
+        let mut map = TileMap::new(4, 3, TileType::Floor);
+        map.set((1, 1), TileType::Wall);
+        map.set((2, 1), TileType::Water);
+        map.set((3, 1), TileType::Chasm);
+        assert!(map.is_passable((0, 0), Legs::Bipedal));
+        assert!(!map.is_passable((1, 1), Legs::Winged));
+        assert!(!map.is_passable((2, 1), Legs::Quadrupedal));
+        assert!(map.is_passable((2, 1), Legs::Slithering));
+        assert!(!map.is_passable((3, 1), Legs::Slithering));
+        assert!(map.is_passable((3, 1), Legs::Winged));
+        assert!(!map.is_passable((4, 1), Legs::Winged));
+        assert!(!map.is_passable((-1, 0), Legs::Bipedal));
+        assert_eq!(map.nearest_passable((1, 1), Legs::Bipedal), Some((0, 0)));
+        assert_eq!(map.nearest_passable((3, 1), Legs::Winged), Some((3, 1)));
+        assert_eq!(map.tiles().nth(6), Some(((2, 1), TileType::Water)));
+    }
+}
@@ -4,19 +4,55 @@ use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
 use std::time::Duration;
 
+mod actions;
 mod brains;
+mod bubbles;
+mod cave;
+mod claims;
+mod clones;
 mod command;
+mod controls;
+mod dungeon;
+mod energy;
+mod events;
+mod history;
+mod legs;
+mod map;
 mod physics;
+mod preview;
+mod stats;
 mod time;
+mod timeline;
 mod visual;
 
+pub use self::cave::{Cave, CaveParams};
+pub use self::clones::Paradox;
 pub use self::command::GameCommand;
-pub use self::physics::{Direction, Position};
+pub use self::controls::TimeControl;
+pub use self::dungeon::{Dungeon, Level};
+pub use self::energy::ChronoEnergy;
+pub use self::events::{EventId, EventScheduler, TimedEvent};
+pub use self::history::{Journal, Spawn};
+pub use self::map::{TileMap, TileType};
+pub use self::physics::{Direction, Movable, Position};
+pub use self::preview::{Ghost, Preview};
+pub use self::time::{
+    seconds, DirectedTime, Instant, SimulationMode, Timekeeper, TimingData, TimingRegistry,
+    TICKS_PER_SECOND,
+};
+pub use self::timeline::Timeline;
 pub use self::visual::BaseSprite;
 
+// How far back time can be rewound; anything older gets discarded.
+const HISTORY_HORIZON_SECS: u64 = 120;
+const CLONE_ALPHA: f32 = 0.6;
+
 pub struct GameState<'a, 'b> {
     dispatcher: Dispatcher<'a, 'b>,
     world: World,
+    paused: bool,
+    // Built on first use and kept around, see `preview()`.
+    preview: Option<Box<GameState<'a, 'b>>>,
 }
 
 struct DispatcherBuilderWrapper<'a, 'b>(DispatcherBuilder<'a, 'b>);
@@ -35,60 +71,278 @@ impl<'a, 'b> DispatcherBuilderWrapper<'a, 'b> {
 }
 
 impl<'a, 'b> GameState<'a, 'b> {
-    pub fn new() -> GameState<'a, 'b> {
+    pub fn new(seed: u64) -> GameState<'a, 'b> {
+        use self::legs::*;
+        use self::physics::*;
+        use self::stats::*;
+        use self::visual::*;
+        use assets::DrawableHandle;
+        use ggez::graphics::Color;
+
+        let mut state = GameState::empty();
+        let mut dungeon = Dungeon::new();
+        for z in 0..dungeon::DUNGEON_DEPTH {
+            // Nothing to climb out of the top level with, or to fall through the bottom one.
+            let params = CaveParams {
+                ropes: if z == 0 { 0 } else { 1 },
+                holes: if z == dungeon::DUNGEON_DEPTH - 1 {
+                    0
+                } else {
+                    1
+                },
+                ..CaveParams::default()
+            };
+            let cave = Cave::generate(dungeon::level_seed(seed, z), &params);
+            if z == 0 {
+                state.spawn_player(cave.stairs_up);
+            }
+            for &(x, y) in &cave.items {
+                history::spawn(&state.world)
+                    .with(Position::on_level(x, y, z, Direction::None))
+                    .with(BaseSprite {
+                        drawable: DrawableHandle::Box,
+                        color: Color::from([1.0, 0.8, 0.0, 1.0]),
+                    })
+                    .build();
+            }
+            for &(x, y) in &cave.monsters {
+                history::spawn(&state.world)
+                    .with(Position::on_level(x, y, z, Direction::None))
+                    .with(Movable::default())
+                    .with(Stats::default())
+                    .with(Legs::Quadrupedal)
+                    .with(BaseSprite {
+                        drawable: DrawableHandle::Circle,
+                        color: Color::from([1.0, 0.2, 0.2, 1.0]),
+                    })
+                    .build();
+            }
+            dungeon.push(Level {
+                map: cave.map,
+                stairs_up: cave.stairs_up,
+                stairs_down: cave.stairs_down,
+            });
+        }
+        state.world.add_resource(dungeon);
+        state
+    }
+
+    fn spawn_player(&self, (x, y): claims::Tile) -> Entity {
+        use self::brains::*;
+        use self::legs::*;
+        use self::physics::*;
+        use self::stats::*;
+        use self::visual::*;
+        use assets::DrawableHandle;
+        use ggez::graphics::Color;
+
+        history::spawn(&self.world)
+            .with(Position::new(x, y, Direction::None))
+            .with(Movable::default())
+            .with(Stats::default())
+            .with(Legs::Bipedal)
+            .with(BaseSprite {
+                drawable: DrawableHandle::Circle,
+                color: Color::from([0.0, 1.0, 1.0, 1.0]),
+            })
+            .with(PlayerBrain {})
+            .build()
+    }
+
+    fn empty() -> GameState<'a, 'b> {
         let mut world = World::new();
         world.register::<physics::Position>();
         world.register::<visual::BaseSprite>();
 
         let mut dispatcher = DispatcherBuilderWrapper(DispatcherBuilder::new())
+            .with(history::module_systems)
+            .with(energy::module_systems)
+            .with(timeline::module_systems)
+            .with(dungeon::module_systems)
+            .with(bubbles::module_systems)
             .with(brains::module_systems)
             .with(physics::module_systems)
+            .with(clones::module_systems)
             .build();
         dispatcher.setup(&mut world.res);
+        world.add_resource(events::EventScheduler::new());
+        world.add_resource(controls::TimeControls::new());
+        world
+            .write_resource::<time::Timekeeper>()
+            .set_history_horizon(Some(Duration::from_secs(HISTORY_HORIZON_SECS)));
 
-        {
-            use self::brains::*;
-            use self::physics::*;
-            use self::visual::*;
-            use assets::DrawableHandle;
-            use ggez::graphics::Color;
-
-            world
-                .create_entity()
-                .with(Position::new(5, 5, Direction::None))
-                .with(Movable::default())
-                .with(BaseSprite {
-                    drawable: DrawableHandle::Circle,
-                    color: Color::from([0.0, 1.0, 1.0, 1.0]),
-                })
-                .with(PlayerBrain {})
-                .build();
-
-            world
-                .create_entity()
-                .with(Position::new(10, 5, Direction::None))
-                .with(BaseSprite {
-                    drawable: DrawableHandle::Box,
-                    color: Color::from([1.0, 0.0, 1.0, 1.0]),
-                })
-                .build();
-        }
-
-        GameState { dispatcher, world }
+        GameState {
+            dispatcher,
+            world,
+            paused: false,
+            preview: None,
+        }
     }
 
     pub fn update(&mut self, d_time: Duration) {
+        if self.paused {
+            return;
+        }
+        controls::run(&self.world);
         self.world
             .write_resource::<time::Timekeeper>()
             .update_real_time(d_time);
+        self.run_systems();
+    }
+
+    pub fn tick(&mut self) {
+        if self.paused {
+            return;
+        }
+        controls::run(&self.world);
+        self.world.write_resource::<time::Timekeeper>().tick();
+        self.run_systems();
+    }
+
+    fn run_systems(&mut self) {
+        self.fork_for_commands();
+        events::run(&mut self.world);
         self.dispatcher.dispatch(&mut self.world.res);
         self.world.maintain();
     }
 
+    // Acting in the past branches off; doing it before anything runs lets journals and
+    // events undo what the abandoned future did at this very instant before the commands
+    // are carried out.
+    fn fork_for_commands(&self) {
+        if self
+            .world
+            .read_resource::<command::GameCommandQueue>()
+            .is_empty()
+        {
+            return;
+        }
+        let now = self.world.read_resource::<time::Timekeeper>().now();
+        let mut timeline = self.world.write_resource::<timeline::Timeline>();
+        if now < timeline.frontier() {
+            timeline.fork(now);
+        }
+    }
+
+    pub fn set_simulation_mode(&mut self, mode: SimulationMode) {
+        self.world
+            .write_resource::<time::Timekeeper>()
+            .set_mode(mode);
+    }
+
+    pub fn set_time_control(&mut self, control: TimeControl) {
+        let time = self.world.read_resource::<time::Timekeeper>();
+        let timeline = self.world.read_resource::<timeline::Timeline>();
+        let energy = self.world.read_resource::<energy::ChronoEnergy>();
+        self.world
+            .write_resource::<controls::TimeControls>()
+            .set(control, &time, &timeline, &energy);
+    }
+
+    pub fn seek(&mut self, target: time::Instant) {
+        let time = self.world.read_resource::<time::Timekeeper>();
+        let timeline = self.world.read_resource::<timeline::Timeline>();
+        let energy = self.world.read_resource::<energy::ChronoEnergy>();
+        self.world
+            .write_resource::<controls::TimeControls>()
+            .seek(target, &time, &timeline, &energy);
+    }
+
+    // Queued commands are kept until the game is unpaused.
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
     pub fn get_world(&self) -> &World {
         &self.world
     }
 
+    pub fn spawn(&self) -> Spawn {
+        history::spawn(&self.world)
+    }
+
+    pub fn despawn(&self, entity: Entity) {
+        history::despawn(&self.world, entity);
+    }
+
+    // Starts or stops recording the player's commands, for a clone to replay later.
+    pub fn set_recording(&mut self, recording: bool) {
+        let mut recorder = self.world.write_resource::<clones::CommandRecorder>();
+        if recording == recorder.is_recording() {
+            return;
+        }
+        if !recording {
+            recorder.stop();
+            return;
+        }
+        let now = self.world.read_resource::<time::Timekeeper>().now();
+        let brain_s = self.world.read_storage::<brains::PlayerBrain>();
+        let position_s = self.world.read_storage::<physics::Position>();
+        if let Some((entity, _, position)) = (&*self.world.entities(), &brain_s, &position_s)
+            .join()
+            .next()
+        {
+            recorder.start(entity, now, position.clone());
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.world
+            .read_resource::<clones::CommandRecorder>()
+            .is_recording()
+    }
+
+    // Time has to be rewound to where the last recording started for its clone to appear.
+    pub fn spawn_clone(&mut self) -> Option<Entity> {
+        use self::clones::CloneBrain;
+        use self::legs::Legs;
+        use self::stats::Stats;
+
+        let recording = self
+            .world
+            .read_resource::<clones::CommandRecorder>()
+            .finished()
+            .cloned()?;
+        {
+            let now = self.world.read_resource::<time::Timekeeper>().now();
+            if now > recording.start() {
+                return None;
+            }
+            let mut timeline = self.world.write_resource::<timeline::Timeline>();
+            if now < timeline.frontier() {
+                timeline.fork(now);
+            }
+        }
+        let original = recording.entity();
+        let stats = self.world.read_storage::<Stats>().get(original).cloned();
+        let legs = self.world.read_storage::<Legs>().get(original).cloned();
+        let sprite = self
+            .world
+            .read_storage::<visual::BaseSprite>()
+            .get(original)
+            .cloned();
+        let mut spawn = history::spawn(&self.world)
+            .with(recording.origin().clone())
+            .with(Movable::default())
+            .with(stats.unwrap_or_default())
+            .with(legs.unwrap_or_default())
+            .with(CloneBrain::new(&recording));
+        if let Some(mut sprite) = sprite {
+            sprite.color.a *= CLONE_ALPHA;
+            spawn = spawn.with(sprite);
+        }
+        Some(spawn.build())
+    }
+
+    // Paradoxes caused by clones since this was last called.
+    pub fn take_paradoxes(&mut self) -> Vec<Paradox> {
+        self.world.write_resource::<clones::Paradoxes>().take()
+    }
+
     pub fn queue_command(&self, command: Option<command::GameCommand>) {
         if let Some(command) = command {
             self.world
@@ -102,9 +356,459 @@ impl<'a, 'b> GameState<'a, 'b> {
 mod tests {
     use super::*;
 
+    fn room() -> Level {
+        let mut map = TileMap::new(32, 24, TileType::Wall);
+        map.fill((1, 1), (30, 22), TileType::Floor);
+        Level {
+            map,
+            stairs_up: (1, 1),
+            stairs_down: (30, 22),
+        }
+    }
+
+    // Single level open room, with the player at (5, 5) and a box at (10, 5).
+    fn test_state<'a, 'b>() -> GameState<'a, 'b> {
+        use assets::DrawableHandle;
+        use ggez::graphics::Color;
+
+        let mut state = GameState::empty();
+        let mut dungeon = Dungeon::new();
+        dungeon.push(room());
+        state.world.add_resource(dungeon);
+        state.spawn_player((5, 5));
+        history::spawn(&state.world)
+            .with(Position::new(10, 5, Direction::None))
+            .with(BaseSprite {
+                drawable: DrawableHandle::Box,
+                color: Color::from([1.0, 0.0, 1.0, 1.0]),
+            })
+            .build();
+        state
+    }
+
     #[test]
     fn tick_once() {
-        let mut state = GameState::new();
+        let mut state = GameState::new(1234);
         state.update(Duration::from_secs(1));
     }
+
+    #[test]
+    fn paused() {
+        let mut state = test_state();
+        state.set_paused(true);
+        state.queue_command(Some(GameCommand::Move(Direction::E)));
+        state.tick();
+        assert_eq!(
+            state.world.read_resource::<time::Timekeeper>().now(),
+            time::Instant::default()
+        );
+        assert!(state
+            .world
+            .write_resource::<command::GameCommandQueue>()
+            .pop()
+            .is_some());
+    }
+
+    #[test]
+    fn rewind_and_resume() {
+        let mut state = test_state();
+        state.queue_command(Some(GameCommand::Move(Direction::E)));
+        for _ in 0..TICKS_PER_SECOND {
+            state.tick();
+        }
+        let frontier = state.world.read_resource::<time::Timekeeper>().now();
+        assert!(frontier > time::Instant::default());
+
+        state.set_time_control(TimeControl::Rewind);
+        state.tick();
+        assert_eq!(
+            state
+                .world
+                .read_resource::<time::Timekeeper>()
+                .exact_time_factor(),
+            time::TimeFactor::new(7, 8)
+        );
+        for _ in 0..TICKS_PER_SECOND {
+            state.tick();
+        }
+        assert!(state.world.read_resource::<time::Timekeeper>().now() < frontier);
+
+        state.set_time_control(TimeControl::Play);
+        for _ in 0..(2 * TICKS_PER_SECOND) {
+            state.tick();
+        }
+        let time = state.world.read_resource::<time::Timekeeper>();
+        assert_eq!(time.now(), frontier);
+        assert_eq!(time.exact_time_factor(), time::TimeFactor::default());
+        assert_eq!(time.scrub_target(), None);
+    }
+
+    #[test]
+    fn step_back_holds() {
+        let mut state = test_state();
+        let root = state.world.read_resource::<Timeline>().current();
+        state.queue_command(Some(GameCommand::Move(Direction::E)));
+        for _ in 0..TICKS_PER_SECOND {
+            state.tick();
+        }
+
+        state.set_time_control(TimeControl::StepBack);
+        for _ in 0..(2 * TICKS_PER_SECOND) {
+            state.tick();
+        }
+        {
+            let time = state.world.read_resource::<time::Timekeeper>();
+            assert_eq!(time.now(), time::Instant::default());
+            assert_eq!(time.scrub_target(), None);
+        }
+        assert_eq!(
+            player_position(&state),
+            Position::new(5, 5, Direction::None)
+        );
+
+        state.queue_command(Some(GameCommand::Move(Direction::E)));
+        for _ in 0..TICKS_PER_SECOND {
+            state.tick();
+        }
+        assert_ne!(state.world.read_resource::<Timeline>().current(), root);
+        assert_eq!(player_position(&state), Position::new(6, 5, Direction::E));
+    }
+
+    fn player_position(state: &GameState) -> Position {
+        let brain_s = state.world.read_storage::<brains::PlayerBrain>();
+        let position_s = state.world.read_storage::<Position>();
+        (&brain_s, &position_s).join().next().unwrap().1.clone()
+    }
+
+    #[test]
+    fn movement() {
+        let mut state = test_state();
+        state.queue_command(Some(GameCommand::Move(Direction::E)));
+        state.update(Duration::from_secs(0));
+        state.update(Duration::from_millis(200));
+        assert_eq!(
+            player_position(&state),
+            Position::new(5, 5, Direction::None)
+        );
+        state.update(Duration::from_millis(200));
+        assert_eq!(player_position(&state), Position::new(6, 5, Direction::E));
+
+        state.set_time_control(TimeControl::Rewind);
+        for _ in 0..TICKS_PER_SECOND {
+            state.update(Duration::from_millis(50));
+        }
+        assert_eq!(
+            state.world.read_resource::<time::Timekeeper>().now(),
+            time::Instant::default()
+        );
+        assert_eq!(
+            player_position(&state),
+            Position::new(5, 5, Direction::None)
+        );
+
+        state.set_time_control(TimeControl::Play);
+        for _ in 0..TICKS_PER_SECOND {
+            state.update(Duration::from_millis(50));
+        }
+        assert_eq!(player_position(&state), Position::new(6, 5, Direction::E));
+    }
+
+    #[test]
+    fn walls_block() {
+        let mut state = test_state();
+        state
+            .world
+            .write_resource::<Dungeon>()
+            .level_mut(0)
+            .unwrap()
+            .map
+            .set((6, 5), TileType::Wall);
+        state.queue_command(Some(GameCommand::Move(Direction::E)));
+        state.update(Duration::from_secs(0));
+        state.update(Duration::from_secs(1));
+        assert_eq!(
+            player_position(&state),
+            Position::new(5, 5, Direction::None)
+        );
+        assert_eq!(
+            state.world.read_resource::<time::Timekeeper>().now(),
+            time::Instant::default()
+        );
+    }
+
+    #[test]
+    fn levels() {
+        let mut state = test_state();
+        {
+            let mut dungeon = state.world.write_resource::<Dungeon>();
+            dungeon
+                .level_mut(0)
+                .unwrap()
+                .map
+                .set((6, 5), TileType::StairsDown);
+            let mut below = room();
+            below.stairs_up = (3, 3);
+            below.map.set((3, 3), TileType::StairsUp);
+            dungeon.push(below);
+        }
+        state.queue_command(Some(GameCommand::Move(Direction::E)));
+        state.update(Duration::from_secs(0));
+        state.update(Duration::from_secs(1));
+        state.queue_command(Some(GameCommand::Move(Direction::D)));
+        state.update(Duration::from_secs(0));
+        state.update(Duration::from_secs(1));
+        assert_eq!(
+            player_position(&state),
+            Position::on_level(3, 3, 1, Direction::D)
+        );
+        state.update(Duration::from_secs(0));
+        assert_eq!(state.world.read_resource::<Dungeon>().active(), 1);
+
+        state.set_time_control(TimeControl::Rewind);
+        for _ in 0..(2 * TICKS_PER_SECOND) {
+            state.update(Duration::from_millis(50));
+        }
+        assert_eq!(
+            state.world.read_resource::<time::Timekeeper>().now(),
+            time::Instant::default()
+        );
+        assert_eq!(
+            player_position(&state),
+            Position::new(5, 5, Direction::None)
+        );
+        assert_eq!(state.world.read_resource::<Dungeon>().active(), 0);
+    }
+
+    #[test]
+    fn rewind_needs_energy() {
+        let mut state = test_state();
+        state.queue_command(Some(GameCommand::Move(Direction::E)));
+        for _ in 0..TICKS_PER_SECOND {
+            state.tick();
+        }
+        let frontier = state.world.read_resource::<time::Timekeeper>().now();
+        state
+            .world
+            .write_resource::<ChronoEnergy>()
+            .set_capacity(Duration::from_millis(100));
+
+        state.set_time_control(TimeControl::Rewind);
+        for _ in 0..TICKS_PER_SECOND {
+            state.tick();
+        }
+        let now = state.world.read_resource::<time::Timekeeper>().now();
+        assert!(now < frontier);
+        // Overshoots by no more than a single tick.
+        assert!(frontier.since(now) < Duration::from_millis(150));
+
+        for _ in 0..(2 * TICKS_PER_SECOND) {
+            state.tick();
+        }
+        assert_eq!(
+            state.world.read_resource::<time::Timekeeper>().now(),
+            frontier
+        );
+        // Replaying doesn't pay anything back, only going past the frontier does.
+        assert!(state.world.read_resource::<ChronoEnergy>().is_empty());
+        state.set_time_control(TimeControl::Rewind);
+        state.tick();
+        assert_eq!(
+            state.world.read_resource::<time::Timekeeper>().now(),
+            frontier
+        );
+
+        state.queue_command(Some(GameCommand::Move(Direction::E)));
+        for _ in 0..TICKS_PER_SECOND {
+            state.tick();
+        }
+        assert!(state.world.read_resource::<time::Timekeeper>().now() > frontier);
+        assert!(!state.world.read_resource::<ChronoEnergy>().is_empty());
+    }
+
+    #[test]
+    fn stop_refunds() {
+        let mut state = test_state();
+        state.queue_command(Some(GameCommand::Move(Direction::E)));
+        for _ in 0..6 {
+            state.tick();
+        }
+        state.queue_command(Some(GameCommand::Stop));
+        state.tick();
+        let stopped = state.world.read_resource::<time::Timekeeper>().now();
+        assert_eq!(
+            stopped,
+            time::Instant::default() + Duration::from_millis(100)
+        );
+        state.tick();
+        let time = state.world.read_resource::<time::Timekeeper>();
+        assert_eq!(time.remaining_simulation_time(), Duration::from_secs(0));
+        assert_eq!(time.now(), stopped);
+    }
+
+    #[test]
+    fn preview_leaves_world_alone() {
+        let mut state = test_state();
+        state.tick();
+        let player = {
+            let brain_s = state.world.read_storage::<brains::PlayerBrain>();
+            (&*state.world.entities(), &brain_s)
+                .join()
+                .next()
+                .unwrap()
+                .0
+        };
+        // Second time around, the reused preview world hands out recycled entities.
+        for _ in 0..2 {
+            let preview = state.preview(GameCommand::Move(Direction::E));
+            assert_eq!(
+                preview.end,
+                time::Instant::default() + Duration::from_millis(250)
+            );
+            assert_eq!(preview.ghosts.len(), 1);
+            assert_eq!(preview.ghosts[0].entity, player);
+            assert_eq!(
+                preview.ghosts[0].position,
+                Position::new(6, 5, Direction::E)
+            );
+        }
+        assert_eq!(
+            player_position(&state),
+            Position::new(5, 5, Direction::None)
+        );
+        let time = state.world.read_resource::<time::Timekeeper>();
+        assert_eq!(time.now(), time::Instant::default());
+        assert_eq!(time.remaining_simulation_time(), Duration::from_secs(0));
+    }
+
+    // Records a move, rewinds to before it and brings in a clone to replay it.
+    fn rewound_with_clone<'a, 'b>(player_moves: bool) -> (GameState<'a, 'b>, Entity) {
+        let mut state = test_state();
+        state.set_recording(true);
+        state.queue_command(Some(GameCommand::Move(Direction::E)));
+        for _ in 0..TICKS_PER_SECOND {
+            state.tick();
+        }
+        state.set_recording(false);
+        state.seek(time::Instant::default());
+        for _ in 0..(4 * TICKS_PER_SECOND) {
+            state.tick();
+        }
+        assert_eq!(
+            state.world.read_resource::<time::Timekeeper>().now(),
+            time::Instant::default()
+        );
+        let clone = state.spawn_clone().unwrap();
+        if player_moves {
+            state.queue_command(Some(GameCommand::Move(Direction::E)));
+        }
+        state.tick();
+        (state, clone)
+    }
+
+    #[test]
+    fn fork_at_action_start() {
+        let mut state = test_state();
+        state.queue_command(Some(GameCommand::Move(Direction::E)));
+        for _ in 0..TICKS_PER_SECOND {
+            state.tick();
+        }
+        state.seek(time::Instant::default());
+        for _ in 0..(4 * TICKS_PER_SECOND) {
+            state.tick();
+        }
+        assert_eq!(
+            state.world.read_resource::<time::Timekeeper>().now(),
+            time::Instant::default()
+        );
+
+        // The move east started at this very instant, so it's gone on the new branch.
+        state.queue_command(Some(GameCommand::Move(Direction::S)));
+        for _ in 0..TICKS_PER_SECOND {
+            state.tick();
+        }
+        assert_eq!(player_position(&state), Position::new(5, 6, Direction::S));
+        let timeline = state.world.read_resource::<Timeline>();
+        let tile_claims = state.world.read_resource::<claims::TileClaims>();
+        assert_eq!(
+            tile_claims.holder(&timeline, (6, 5, 0), time::Instant::default()),
+            None
+        );
+        assert_eq!(
+            state
+                .world
+                .read_resource::<time::TimingData<Movable>>()
+                .spans(&timeline)
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn clone_replay() {
+        let (mut state, clone) = rewound_with_clone(false);
+        assert_eq!(
+            state.world.read_storage::<Movable>().get(clone),
+            Some(&Movable::new(Direction::E))
+        );
+        assert!(state.take_paradoxes().is_empty());
+
+        let (mut state, clone) = rewound_with_clone(true);
+        assert_eq!(
+            state.world.read_storage::<Movable>().get(clone),
+            Some(&Movable::new(Direction::None))
+        );
+        let paradoxes = state.take_paradoxes();
+        assert_eq!(paradoxes.len(), 1);
+        assert_eq!(paradoxes[0].entity, clone);
+    }
+
+    #[test]
+    fn clone_replay_on_time() {
+        let mut state = test_state();
+        state.set_recording(true);
+        state.queue_command(Some(GameCommand::Move(Direction::E)));
+        for _ in 0..6 {
+            state.tick();
+        }
+        state.queue_command(Some(GameCommand::Stop));
+        state.tick();
+        state.set_recording(false);
+        state.seek(time::Instant::default());
+        for _ in 0..(4 * TICKS_PER_SECOND) {
+            state.tick();
+        }
+        state.spawn_clone().unwrap();
+
+        // Time ramps back up from a standstill, so ticks no longer land on the recorded stop.
+        state.queue_command(Some(GameCommand::Move(Direction::W)));
+        for _ in 0..TICKS_PER_SECOND {
+            state.tick();
+        }
+        assert!(state.take_paradoxes().is_empty());
+    }
+
+    #[test]
+    fn deterministic_ticks() {
+        let run = || {
+            let mut state = test_state();
+            state
+                .world
+                .write_resource::<time::Timekeeper>()
+                .set_exact_time_factor(time::TimeFactor::new(1, 3));
+            state.queue_command(Some(GameCommand::Move(Direction::E)));
+            // First tick only schedules the move.
+            state.tick();
+            for _ in 0..30 {
+                state.tick();
+            }
+            let now = state.world.read_resource::<time::Timekeeper>().now();
+            now
+        };
+        let first = run();
+        assert_eq!(first, run());
+        assert_eq!(
+            time::Instant::default().compare_to(first),
+            time::DirectedTime::Future(Duration::from_nanos(166_666_666))
+        );
+    }
 }
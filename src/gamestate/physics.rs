@@ -1,13 +1,20 @@
 use specs::prelude::*;
+use std::cmp::max;
 
+use super::dungeon::Dungeon;
+use super::history::{Historical, Journal};
+use super::legs::Legs;
 use super::time::*;
+use super::timeline::Timeline;
 
 pub fn module_systems<'a, 'b>(builder: DispatcherBuilder<'a, 'b>) -> DispatcherBuilder<'a, 'b> {
-    builder.with(
-        TimingSystem::<Movable>::new(),
-        "movable_timing",
-        &["player_commands"],
-    )
+    builder
+        .with(MovementSystem, "movement", &["player_commands"])
+        .with(
+            TimingSystem::<Movable>::new(),
+            "movable_timing",
+            &["movement"],
+        )
 }
 
 #[allow(dead_code)]
@@ -43,21 +50,43 @@ impl Direction {
             Direction::SW => Direction::NE,
             Direction::W => Direction::E,
             Direction::NW => Direction::SE,
-            _ => unimplemented!(),
+            Direction::U => Direction::D,
+            Direction::D => Direction::U,
+            Direction::None => Direction::None,
+        }
+    }
+
+    pub fn offset(self) -> (i32, i32) {
+        match self {
+            Direction::N => (0, -1),
+            Direction::NE => (1, -1),
+            Direction::E => (1, 0),
+            Direction::SE => (1, 1),
+            Direction::S => (0, 1),
+            Direction::SW => (-1, 1),
+            Direction::W => (-1, 0),
+            Direction::NW => (-1, -1),
+            Direction::U | Direction::D | Direction::None => (0, 0),
         }
     }
 }
 
-#[derive(Component, Debug, Default)]
+// `z` is the dungeon level, counting down from the surface.
+#[derive(Component, Debug, Default, Clone, PartialEq)]
 pub struct Position {
     x: i32,
     y: i32,
+    z: i32,
     r: Direction,
 }
 
 impl Position {
     pub fn new(x: i32, y: i32, r: Direction) -> Position {
-        Position { x, y, r }
+        Position::on_level(x, y, 0, r)
+    }
+
+    pub fn on_level(x: i32, y: i32, z: i32, r: Direction) -> Position {
+        Position { x, y, z, r }
     }
 
     pub fn x(&self) -> i32 {
@@ -68,12 +97,36 @@ impl Position {
         self.y
     }
 
+    pub fn z(&self) -> i32 {
+        self.z
+    }
+
     pub fn r(&self) -> Direction {
         self.r
     }
+
+    pub fn tile(&self) -> (i32, i32) {
+        (self.x, self.y)
+    }
+
+    pub fn cell(&self) -> (i32, i32, i32) {
+        (self.x, self.y, self.z)
+    }
+
+    // Stays on the same level; going up or down is up to `Dungeon::destination()`.
+    pub fn moved(&self, direction: Direction) -> Position {
+        let (dx, dy) = direction.offset();
+        Position::on_level(self.x + dx, self.y + dy, self.z, direction)
+    }
+
+    pub fn distance(&self, other: &Position) -> i32 {
+        max((self.x - other.x).abs(), (self.y - other.y).abs())
+    }
 }
 
-#[derive(Component, Debug, Default)]
+impl Historical for Position {}
+
+#[derive(Component, Debug, Default, Clone, PartialEq)]
 pub struct Movable {
     direction: Direction,
 }
@@ -83,25 +136,119 @@ impl Movable {
         Movable { direction }
     }
 
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    // Action still takes its time, but goes nowhere.
+    pub fn bump(&mut self) {
+        self.direction = Direction::None;
+    }
+
     pub fn start_moving(
         &mut self,
         entity: &Entity,
         time: &Timekeeper,
+        timeline: &Timeline,
         timing_data: &mut TimingData<Movable>,
         direction: Direction,
         duration: Duration,
-    ) {
+    ) -> Option<Duration> {
+        let duration = self.schedule(entity, time, timeline, timing_data, duration)?;
         if let DirectedTime::Past(_) = time.delta() {
             self.direction = direction.invert();
         } else {
             self.direction = direction;
         }
-        self.schedule(entity, time, timing_data, duration);
+        Some(duration)
+    }
+
+    // An interrupted move never reaches its target tile.
+    pub fn stop(
+        &mut self,
+        entity: &Entity,
+        time: &Timekeeper,
+        timeline: &Timeline,
+        timing_data: &mut TimingData<Movable>,
+    ) -> Option<Duration> {
+        let unused = self.interrupt(entity, time, timeline, timing_data)?;
+        self.bump();
+        Some(unused)
     }
 }
 
 impl Timed for Movable {}
 
+impl Historical for Movable {}
+
+// Puts entities on their target tiles once their moves complete.
+struct MovementSystem;
+
+#[derive(SystemData)]
+struct MovementData<'a> {
+    time: Read<'a, Timekeeper>,
+    timeline: Read<'a, Timeline>,
+    dungeon: Read<'a, Dungeon>,
+    movable: ReadStorage<'a, Movable>,
+    movable_timing: Read<'a, TimingData<Movable>>,
+    legs: ReadStorage<'a, Legs>,
+    position: WriteStorage<'a, Position>,
+    position_journal: Write<'a, Journal<Position>>,
+}
+
+impl<'a> System<'a> for MovementSystem {
+    type SystemData = MovementData<'a>;
+
+    // Arrivals are journaled at the instant the move ends, so rewinding through it undoes
+    // them; replaying already simulated time redoes them from the journal instead.
+    fn run(&mut self, mut data: Self::SystemData) {
+        match data.time.delta() {
+            DirectedTime::Future(_) => (),
+            _ => return,
+        }
+        let completed =
+            data.movable_timing
+                .completed(&data.timeline, data.time.previous(), data.time.now());
+        for (entity, end) in completed {
+            let direction = match data.movable.get(entity) {
+                Some(movable) if movable.direction() != Direction::None => movable.direction(),
+                _ => continue,
+            };
+            let legs = data.legs.get(entity).cloned().unwrap_or_default();
+            // Only this very move having been journaled already counts; other position
+            // changes landing on the same instant don't.
+            let dungeon = &data.dungeon;
+            let arrived =
+                |before: Option<&Position>, after: Option<&Position>| match (before, after) {
+                    (Some(before), Some(after)) => {
+                        dungeon.destination(before, direction, legs).as_ref() == Some(after)
+                    }
+                    _ => false,
+                };
+            if data
+                .position_journal
+                .has_change_where(&data.timeline, entity, end, arrived)
+            {
+                continue;
+            }
+            let destination = match data
+                .position
+                .get(entity)
+                .and_then(|position| data.dungeon.destination(position, direction, legs))
+            {
+                Some(destination) => destination,
+                None => continue,
+            };
+            data.position_journal.modify(
+                data.timeline.moment_at(end),
+                entity,
+                &mut data.position,
+                |position| *position = destination,
+            );
+        }
+    }
+}
+
 mod tests {
     use super::*;
 }
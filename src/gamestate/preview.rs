@@ -0,0 +1,120 @@
+use specs::prelude::*;
+use std::collections::HashMap;
+
+use super::claims::TileClaims;
+use super::clones::{CommandRecorder, Paradoxes};
+use super::command::GameCommand;
+use super::controls::TimeControls;
+use super::dungeon::Dungeon;
+use super::energy::ChronoEnergy;
+use super::events::EventScheduler;
+use super::history::Journals;
+use super::physics::Position;
+use super::time::*;
+use super::timeline::Timeline;
+use super::visual::BaseSprite;
+use super::GameState;
+
+// In case whatever is previewed never runs out of time to spend.
+const MAX_PREVIEW_STEPS: usize = 1000;
+
+#[derive(Debug, Clone)]
+pub struct Ghost {
+    pub entity: Entity,
+    pub position: Position,
+    pub sprite: BaseSprite,
+}
+
+#[derive(Debug, Clone)]
+pub struct Preview {
+    pub end: Instant,
+    pub ghosts: Vec<Ghost>,
+}
+
+impl<'a, 'b> GameState<'a, 'b> {
+    // Plays `command` out on a copy of the world, leaving the real one untouched. Everyone
+    // who ends up somewhere else than they are now gets a ghost.
+    pub fn preview(&mut self, command: GameCommand) -> Preview {
+        let mut preview = self
+            .preview
+            .take()
+            .unwrap_or_else(|| Box::new(GameState::empty()));
+        let entities = self.copy_into(&mut preview.world);
+        {
+            let mut time = preview.world.write_resource::<Timekeeper>();
+            time.scrub_to(None);
+            time.set_mode(SimulationMode::TurnBased);
+            time.set_exact_time_factor(TimeFactor::default());
+        }
+        preview.queue_command(Some(command));
+        preview.update(Duration::from_secs(0));
+        for _ in 0..MAX_PREVIEW_STEPS {
+            preview.update(Duration::from_secs(1));
+            if preview.world.read_resource::<Timekeeper>().delta() == DirectedTime::Still {
+                break;
+            }
+        }
+
+        let ghosts = {
+            let positions = self.world.read_storage::<Position>();
+            let preview_positions = preview.world.read_storage::<Position>();
+            let sprites = preview.world.read_storage::<BaseSprite>();
+            (&*self.world.entities())
+                .join()
+                .filter_map(|entity| {
+                    let copy = *entities.get(&entity)?;
+                    let position = preview_positions.get(copy)?;
+                    if positions.get(entity) == Some(position) {
+                        return None;
+                    }
+                    Some(Ghost {
+                        entity,
+                        position: position.clone(),
+                        sprite: sprites.get(copy)?.clone(),
+                    })
+                })
+                .collect()
+        };
+        let end = preview.world.read_resource::<Timekeeper>().now();
+        self.preview = Some(preview);
+        Preview { end, ghosts }
+    }
+
+    // Clears `world` out and fills it with copies of everything in this one; returns which
+    // copy each entity got, since their ids needn't match.
+    fn copy_into(&self, world: &mut World) -> HashMap<Entity, Entity> {
+        let stale = (&*world.entities()).join().collect::<Vec<_>>();
+        if let Err(error) = world.delete_entities(&stale) {
+            warn!("can't clear out preview world: {:?}", error);
+        }
+        world.maintain();
+        let entities = (&*self.world.entities())
+            .join()
+            .map(|entity| (entity, world.create_entity().build()))
+            .collect::<HashMap<_, _>>();
+        self.world
+            .read_resource::<Journals>()
+            .copy(&self.world, world, &entities);
+        self.world
+            .read_resource::<TimingRegistry>()
+            .copy(&self.world.res, &world.res, &entities);
+        {
+            let mut time = world.write_resource::<Timekeeper>();
+            *time = self.world.read_resource::<Timekeeper>().clone();
+            // Keyed by the original entities; recomputed on the first dispatch anyway.
+            time.set_local_factors(HashMap::new());
+        }
+        *world.write_resource::<Timeline>() = self.world.read_resource::<Timeline>().clone();
+        *world.write_resource::<TileClaims>() =
+            self.world.read_resource::<TileClaims>().remapped(&entities);
+        *world.write_resource::<Dungeon>() = self.world.read_resource::<Dungeon>().clone();
+        *world.write_resource::<ChronoEnergy>() =
+            self.world.read_resource::<ChronoEnergy>().clone();
+        *world.write_resource::<EventScheduler>() =
+            self.world.read_resource::<EventScheduler>().clone();
+        *world.write_resource::<TimeControls>() = TimeControls::new();
+        *world.write_resource::<CommandRecorder>() = CommandRecorder::new();
+        *world.write_resource::<Paradoxes>() = Paradoxes::new();
+        entities
+    }
+}
@@ -0,0 +1,88 @@
+use specs::prelude::*;
+
+use super::history::Historical;
+use super::time::TimeFactor;
+
+pub const BASE_SPEED: u32 = 100;
+
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct Stats {
+    speed: u32,
+    capacity: u32,
+}
+
+impl Default for Stats {
+    fn default() -> Self {
+        Stats::new(BASE_SPEED, 50)
+    }
+}
+
+impl Stats {
+    pub fn new(speed: u32, capacity: u32) -> Stats {
+        Stats { speed, capacity }
+    }
+
+    pub fn speed(&self) -> u32 {
+        self.speed
+    }
+
+    pub fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    // Faster entities take proportionally less time; speed of 0 is treated as 1.
+    pub fn speed_factor(&self) -> TimeFactor {
+        TimeFactor::new(i64::from(BASE_SPEED), u64::from(self.speed.max(1)))
+    }
+
+    pub fn encumbrance(&self, equipment: Option<&Equipment>) -> Encumbrance {
+        let weight = equipment.map_or(0, |equipment| equipment.weight());
+        if weight * 2 <= self.capacity {
+            Encumbrance::Unburdened
+        } else if weight <= self.capacity {
+            Encumbrance::Burdened
+        } else if weight <= self.capacity * 2 {
+            Encumbrance::Strained
+        } else {
+            Encumbrance::Overloaded
+        }
+    }
+}
+
+impl Historical for Stats {}
+
+#[derive(Component, Debug, Clone, Default, PartialEq)]
+pub struct Equipment {
+    weight: u32,
+}
+
+impl Equipment {
+    pub fn new(weight: u32) -> Equipment {
+        Equipment { weight }
+    }
+
+    pub fn weight(&self) -> u32 {
+        self.weight
+    }
+}
+
+impl Historical for Equipment {}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Encumbrance {
+    Unburdened,
+    Burdened,
+    Strained,
+    Overloaded,
+}
+
+impl Encumbrance {
+    pub fn factor(self) -> TimeFactor {
+        match self {
+            Encumbrance::Unburdened => TimeFactor::new(1, 1),
+            Encumbrance::Burdened => TimeFactor::new(5, 4),
+            Encumbrance::Strained => TimeFactor::new(3, 2),
+            Encumbrance::Overloaded => TimeFactor::new(2, 1),
+        }
+    }
+}
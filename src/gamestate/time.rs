@@ -1,16 +1,22 @@
 use specs::join::JoinIter;
 use specs::prelude::*;
-use specs::world::Index;
-use std::cmp::min;
-use std::collections::BTreeMap;
+use std::cmp::{max, min};
+use std::collections::{BTreeMap, HashMap};
 use std::marker::PhantomData;
-use std::ops::{Add, Sub};
+use std::ops::Bound::{Excluded, Included, Unbounded};
+use std::ops::{Add, Mul, Sub};
 use std::sync::{Arc, Weak};
 pub use std::time::Duration;
 
+use super::timeline::{Moment, Timeline};
+
 const ZERO_DURATION: Duration = Duration::from_secs(0);
+const NANOS_PER_SEC: u64 = 1_000_000_000;
+const FLOAT_FACTOR_PRECISION: u64 = 1 << 16;
+pub const TICKS_PER_SECOND: u32 = 60;
+const UNLIMITED_DURATION: Duration = Duration::from_secs(::std::u64::MAX);
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Ord, PartialOrd)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Ord, PartialOrd, Default)]
 pub struct Instant(Duration);
 
 impl Instant {
@@ -23,6 +29,10 @@ impl Instant {
             DirectedTime::Past(self.0 - other.0)
         }
     }
+
+    pub fn since(&self, earlier: Instant) -> Duration {
+        self.0.checked_sub(earlier.0).unwrap_or(ZERO_DURATION)
+    }
 }
 
 impl Add<Duration> for Instant {
@@ -41,6 +51,29 @@ impl Sub<Duration> for Instant {
     }
 }
 
+// Time can't be rewound past the timekeeper's rewind limit, so records that lie entirely
+// before it are never looked at again, and get dropped as the limit moves up.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PrunedUntil(Instant);
+
+impl PrunedUntil {
+    // Moves up to `limit`; false if it's no further along, and there's nothing new to drop.
+    pub fn advance(&mut self, limit: Instant) -> bool {
+        if limit <= self.0 {
+            return false;
+        }
+        self.0 = limit;
+        true
+    }
+
+    // Drops the entries keyed before the limit.
+    pub fn split_off<V>(&self, map: &mut BTreeMap<Instant, V>) {
+        if map.keys().next().map_or(false, |&first| first < self.0) {
+            *map = map.split_off(&self.0);
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
 pub enum DirectedTime {
     Future(Duration),
@@ -48,14 +81,138 @@ pub enum DirectedTime {
     Past(Duration),
 }
 
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct TimeFactor {
+    numerator: i64,
+    denominator: u64,
+}
+
+impl Default for TimeFactor {
+    fn default() -> Self {
+        TimeFactor::new(1, 1)
+    }
+}
+
+impl From<f32> for TimeFactor {
+    fn from(factor: f32) -> Self {
+        let numerator = (factor * FLOAT_FACTOR_PRECISION as f32).round() as i64;
+        TimeFactor::new(numerator, FLOAT_FACTOR_PRECISION)
+    }
+}
+
+impl TimeFactor {
+    pub fn new(numerator: i64, denominator: u64) -> TimeFactor {
+        assert_ne!(denominator, 0, "time factor with zero denominator");
+        let divisor = gcd(numerator.abs() as u64, denominator);
+        TimeFactor {
+            numerator: numerator / divisor as i64,
+            denominator: denominator / divisor,
+        }
+    }
+
+    pub fn numerator(&self) -> i64 {
+        self.numerator
+    }
+
+    pub fn denominator(&self) -> u64 {
+        self.denominator
+    }
+
+    pub fn as_f32(&self) -> f32 {
+        self.numerator as f32 / self.denominator as f32
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.numerator == 0
+    }
+
+    pub fn inverse(&self) -> Option<TimeFactor> {
+        if self.is_zero() {
+            None
+        } else {
+            Some(TimeFactor::new(
+                self.numerator.signum() * self.denominator as i64,
+                self.numerator.abs() as u64,
+            ))
+        }
+    }
+
+    // Widened so that large factors saturate instead of overflowing.
+    pub fn scale(&self, duration: Duration) -> Duration {
+        let nanos =
+            u128::from(duration_to_nanos(duration)) * u128::from(self.numerator.abs() as u64);
+        nanos_to_duration(saturate(nanos / u128::from(self.denominator)))
+    }
+
+    // Moves towards `target` by at most `step`.
+    pub fn approach(&self, target: TimeFactor, step: TimeFactor) -> TimeFactor {
+        let difference = TimeFactor::new(
+            target.numerator * self.denominator as i64 - self.numerator * target.denominator as i64,
+            self.denominator * target.denominator,
+        );
+        let step_numerator = step.numerator.abs() as u64;
+        if difference.numerator.abs() as u64 * step.denominator
+            <= step_numerator * difference.denominator
+        {
+            target
+        } else {
+            *self
+                + TimeFactor::new(
+                    difference.numerator.signum() * step_numerator as i64,
+                    step.denominator,
+                )
+        }
+    }
+}
+
+impl Add for TimeFactor {
+    type Output = TimeFactor;
+
+    fn add(self, rhs: TimeFactor) -> TimeFactor {
+        TimeFactor::new(
+            self.numerator * rhs.denominator as i64 + rhs.numerator * self.denominator as i64,
+            self.denominator * rhs.denominator,
+        )
+    }
+}
+
+impl Mul for TimeFactor {
+    type Output = TimeFactor;
+
+    fn mul(self, rhs: TimeFactor) -> TimeFactor {
+        TimeFactor::new(
+            self.numerator * rhs.numerator,
+            self.denominator * rhs.denominator,
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum SimulationMode {
+    // Time only moves as far as actions pay for it with `add_simulation_time`.
+    TurnBased,
+    // Time keeps flowing at the time factor, actions or not.
+    RealTime,
+}
+
+#[derive(Clone)]
 pub struct Timekeeper {
     real_time_delta: Duration,
     remaining_sim_time: Duration,
     sim_delta: DirectedTime,
-    sim_time_factor: f32,
+    sim_time_factor: TimeFactor,
+    sim_carry: u64,
+    sim_carry_denominator: u64,
+    sim_ticks: u64,
     sim_elapsed_time: Duration,
-    sim_closest_future: Duration,
-    sim_closest_past: Duration,
+    sim_furthest_time: Duration,
+    sim_history_horizon: Option<Duration>,
+    sim_closest_future: Option<Instant>,
+    sim_closest_past: Option<Instant>,
+    sim_scrub_target: Option<Instant>,
+    sim_mode: SimulationMode,
+    sim_rewind_locked: bool,
+    local_factors: HashMap<Entity, TimeFactor>,
 }
 
 impl Default for Timekeeper {
@@ -70,60 +227,173 @@ impl Timekeeper {
             real_time_delta: ZERO_DURATION,
             remaining_sim_time: ZERO_DURATION,
             sim_delta: DirectedTime::Still,
-            sim_time_factor: 1.0,
+            sim_time_factor: TimeFactor::default(),
+            sim_carry: 0,
+            sim_carry_denominator: 1,
+            sim_ticks: 0,
             sim_elapsed_time: ZERO_DURATION,
-            sim_closest_future: ZERO_DURATION,
-            sim_closest_past: ZERO_DURATION,
+            sim_furthest_time: ZERO_DURATION,
+            sim_history_horizon: None,
+            sim_closest_future: None,
+            sim_closest_past: None,
+            sim_scrub_target: None,
+            sim_mode: SimulationMode::TurnBased,
+            sim_rewind_locked: false,
+            local_factors: HashMap::new(),
         }
     }
 
     pub fn update_real_time(&mut self, d_time: Duration) {
         self.real_time_delta = d_time;
-        if self.remaining_sim_time > ZERO_DURATION {
-            let adjusted = mul_dur_by_factor(self.real_time_delta, self.sim_time_factor.abs());
-            let signum = self.sim_time_factor.signum();
-            if signum > 0.0 {
-                let time_chunk = min(
-                    adjusted,
-                    if self.sim_closest_future != ZERO_DURATION {
-                        min(
-                            self.remaining_sim_time,
-                            self.sim_closest_future - self.sim_elapsed_time,
-                        )
-                    } else {
-                        self.remaining_sim_time
-                    },
-                );
-                self.remaining_sim_time -= time_chunk;
+        self.advance(duration_to_nanos(d_time), 1);
+    }
+
+    // Fixed step of `1 / TICKS_PER_SECOND` seconds; only integer math is involved, so
+    // the same sequence of ticks and factors always ends up at the same `now()`.
+    pub fn tick(&mut self) {
+        let ticks_per_second = u64::from(TICKS_PER_SECOND);
+        self.sim_ticks += 1;
+        self.real_time_delta = nanos_to_duration(NANOS_PER_SEC / ticks_per_second);
+        self.advance(NANOS_PER_SEC, ticks_per_second);
+    }
+
+    pub fn ticks(&self) -> u64 {
+        self.sim_ticks
+    }
+
+    // Advances by `real_nanos / real_denominator` nanoseconds of real time.
+    fn advance(&mut self, real_nanos: u64, real_denominator: u64) {
+        self.sim_delta = DirectedTime::Still;
+        let budget = self.budget();
+        if budget > ZERO_DURATION && self.sim_time_factor.numerator() != 0 {
+            let adjusted = self.scale_real_time(real_nanos, real_denominator);
+            let mut time_chunk = min(adjusted, budget);
+            if self.sim_time_factor.numerator() > 0 {
+                if let Some(closest) = self.sim_closest_future {
+                    time_chunk = min(time_chunk, closest.0 - self.sim_elapsed_time);
+                }
                 self.sim_elapsed_time += time_chunk;
+                self.sim_furthest_time = max(self.sim_furthest_time, self.sim_elapsed_time);
                 self.sim_delta = DirectedTime::Future(time_chunk);
-            } else if signum < 0.0 {
-                let time_chunk = min(
-                    adjusted,
-                    if self.sim_closest_past != ZERO_DURATION {
-                        min(
-                            self.remaining_sim_time,
-                            self.sim_elapsed_time - self.sim_closest_past,
-                        )
-                    } else {
-                        self.remaining_sim_time
-                    },
-                );
-                self.remaining_sim_time -= time_chunk;
+            } else {
+                time_chunk = min(time_chunk, self.now().since(self.rewind_limit()));
+                if let Some(closest) = self.sim_closest_past {
+                    time_chunk = min(time_chunk, self.sim_elapsed_time - closest.0);
+                }
                 self.sim_elapsed_time -= time_chunk;
                 self.sim_delta = DirectedTime::Past(time_chunk);
-            } else {
-                self.sim_delta = DirectedTime::Still;
             }
-        } else {
-            self.sim_delta = DirectedTime::Still;
+            if time_chunk < adjusted {
+                self.sim_carry = 0;
+            }
+            if self.sim_scrub_target.is_none() {
+                self.refund_simulation_time(time_chunk);
+            }
+        }
+        // Horizon is relative to the old `now`; timing systems recompute it every dispatch.
+        self.sim_closest_future = None;
+        self.sim_closest_past = None;
+    }
+
+    // While scrubbing, time only moves towards the target and play budget is left alone.
+    fn budget(&self) -> Duration {
+        match self.sim_scrub_target {
+            Some(target) if self.sim_time_factor.numerator() > 0 => target.since(self.now()),
+            Some(target) => self.now().since(target),
+            None => match self.sim_mode {
+                SimulationMode::TurnBased => self.remaining_sim_time,
+                SimulationMode::RealTime => UNLIMITED_DURATION,
+            },
+        }
+    }
+
+    // Leftover fractions of a nanosecond are carried over instead of being rounded away.
+    fn scale_real_time(&mut self, real_nanos: u64, real_denominator: u64) -> Duration {
+        let denominator = real_denominator * self.sim_time_factor.denominator();
+        if denominator != self.sim_carry_denominator {
+            self.sim_carry = 0;
+            self.sim_carry_denominator = denominator;
+        }
+        let scaled = u128::from(real_nanos)
+            * u128::from(self.sim_time_factor.numerator().abs() as u64)
+            + u128::from(self.sim_carry);
+        let denominator = u128::from(denominator);
+        self.sim_carry = (scaled % denominator) as u64;
+        nanos_to_duration(saturate(scaled / denominator))
+    }
+
+    pub fn limit_horizon(&mut self, past: Option<Instant>, future: Option<Instant>) {
+        let now = self.now();
+        if let Some(past) = past {
+            if past < now && self.sim_closest_past.map_or(true, |closest| past > closest) {
+                self.sim_closest_past = Some(past);
+            }
+        }
+        if let Some(future) = future {
+            if future > now
+                && self
+                    .sim_closest_future
+                    .map_or(true, |closest| future < closest)
+            {
+                self.sim_closest_future = Some(future);
+            }
         }
     }
 
+    // How far behind the furthest simulated instant history is kept; `None` keeps all of it.
+    pub fn set_history_horizon(&mut self, horizon: Option<Duration>) {
+        self.sim_history_horizon = horizon;
+    }
+
+    pub fn rewind_limit(&self) -> Instant {
+        match self.sim_history_horizon {
+            Some(horizon) => Instant(
+                self.sim_furthest_time
+                    .checked_sub(horizon)
+                    .unwrap_or(ZERO_DURATION),
+            ),
+            None => Instant::default(),
+        }
+    }
+
+    pub fn horizon(&self) -> (Option<Instant>, Option<Instant>) {
+        (self.sim_closest_past, self.sim_closest_future)
+    }
+
     pub fn add_simulation_time(&mut self, d_time: Duration) {
         self.remaining_sim_time += d_time;
     }
 
+    // Gives back time that was granted for an action that got cut short.
+    pub fn refund_simulation_time(&mut self, d_time: Duration) {
+        self.remaining_sim_time = self
+            .remaining_sim_time
+            .checked_sub(d_time)
+            .unwrap_or(ZERO_DURATION);
+    }
+
+    // Either way, whatever is left of `remaining_simulation_time()` keeps being spent.
+    pub fn set_mode(&mut self, mode: SimulationMode) {
+        info!("simulation mode: {:?}", mode);
+        self.sim_mode = mode;
+    }
+
+    pub fn mode(&self) -> SimulationMode {
+        self.sim_mode
+    }
+
+    pub fn remaining_simulation_time(&self) -> Duration {
+        self.remaining_sim_time
+    }
+
+    pub fn scrub_to(&mut self, target: Option<Instant>) {
+        self.sim_scrub_target = target;
+    }
+
+    pub fn scrub_target(&self) -> Option<Instant> {
+        self.sim_scrub_target
+    }
+
     pub fn real_time_delta(&self) -> Duration {
         self.real_time_delta
     }
@@ -133,16 +403,92 @@ impl Timekeeper {
     }
 
     pub fn set_time_factor(&mut self, factor: f32) {
-        self.sim_time_factor = factor;
+        self.set_exact_time_factor(TimeFactor::from(factor));
     }
 
     pub fn time_factor(&self) -> f32 {
+        self.sim_time_factor.as_f32()
+    }
+
+    // Negative factors are held at zero while rewinding is locked.
+    pub fn set_exact_time_factor(&mut self, factor: TimeFactor) {
+        if self.sim_rewind_locked && factor.numerator() < 0 {
+            self.sim_time_factor = TimeFactor::new(0, 1);
+        } else {
+            self.sim_time_factor = factor;
+        }
+    }
+
+    // Locked whenever there's no chrono energy left to rewind with.
+    pub fn lock_rewind(&mut self, locked: bool) {
+        self.sim_rewind_locked = locked;
+        let factor = self.sim_time_factor;
+        self.set_exact_time_factor(factor);
+    }
+
+    pub fn exact_time_factor(&self) -> TimeFactor {
         self.sim_time_factor
     }
 
     pub fn now(&self) -> Instant {
         Instant(self.sim_elapsed_time)
     }
+
+    pub fn previous(&self) -> Instant {
+        match self.sim_delta {
+            DirectedTime::Future(delta) => self.now() - delta,
+            DirectedTime::Past(delta) => self.now() + delta,
+            DirectedTime::Still => self.now(),
+        }
+    }
+
+    // Where an action of `duration` started now lies, given the direction time flows in.
+    // Going backwards, it can't reach past the very beginning.
+    pub fn span(&self, duration: Duration) -> (Instant, Instant) {
+        match self.sim_delta {
+            DirectedTime::Past(_) => (
+                Instant(
+                    self.sim_elapsed_time
+                        .checked_sub(duration)
+                        .unwrap_or(ZERO_DURATION),
+                ),
+                self.now(),
+            ),
+            _ => (self.now(), self.now() + duration),
+        }
+    }
+
+    pub fn set_local_factors(&mut self, factors: HashMap<Entity, TimeFactor>) {
+        self.local_factors = factors;
+    }
+
+    pub fn local_factor(&self, entity: &Entity) -> TimeFactor {
+        self.local_factors.get(entity).cloned().unwrap_or_default()
+    }
+
+    pub fn is_frozen(&self, entity: &Entity) -> bool {
+        self.local_factor(entity).is_zero()
+    }
+
+    // How long `local` time for `entity` takes in world time; `None` for entities in stasis.
+    pub fn to_global(&self, entity: &Entity, local: Duration) -> Option<Duration> {
+        self.local_factor(entity)
+            .inverse()
+            .map(|inverse| inverse.scale(local))
+    }
+
+    pub fn local_delta(&self, entity: &Entity) -> DirectedTime {
+        let factor = self.local_factor(entity);
+        match self.sim_delta {
+            DirectedTime::Future(delta) if !factor.is_zero() => {
+                DirectedTime::Future(factor.scale(delta))
+            }
+            DirectedTime::Past(delta) if !factor.is_zero() => {
+                DirectedTime::Past(factor.scale(delta))
+            }
+            _ => DirectedTime::Still,
+        }
+    }
 }
 
 pub trait Timed: Component {
@@ -150,18 +496,55 @@ pub trait Timed: Component {
         &self,
         entity: &Entity,
         time: &Timekeeper,
+        timeline: &Timeline,
         timing_data: &mut TimingData<Self>,
         duration: Duration,
-    ) {
-        timing_data.schedule(entity, time, duration);
+    ) -> Option<Duration> {
+        timing_data.schedule(entity, time, timeline, duration)
+    }
+
+    fn interrupt(
+        &self,
+        entity: &Entity,
+        time: &Timekeeper,
+        timeline: &Timeline,
+        timing_data: &mut TimingData<Self>,
+    ) -> Option<Duration> {
+        timing_data.interrupt(entity, time, timeline)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Span {
+    start: Instant,
+    end: Instant,
+    scheduled: Moment,
+    interruptions: Vec<Moment>,
+}
+
+impl Span {
+    // Earliest interruption that happened on the current branch cuts the span short.
+    fn end(&self, timeline: &Timeline) -> Instant {
+        self.interruptions
+            .iter()
+            .filter(|&&moment| timeline.is_visible(moment))
+            .map(|moment| moment.instant())
+            .fold(self.end, min)
+    }
+
+    fn is_active(&self, timeline: &Timeline, now: Instant) -> bool {
+        timeline.is_visible(self.scheduled) && self.start <= now && now < self.end(timeline)
     }
 }
 
 pub struct TimingData<T> {
     phantom_data: PhantomData<T>,
+    // Rebuilt from living entities every dispatch, so a recycled index can't inherit it.
     should_update: BitSet,
-    starts: BTreeMap<Instant, Vec<Index>>,
-    ends: BTreeMap<Instant, Vec<Index>>,
+    starts: BTreeMap<Instant, Vec<(Entity, Moment)>>,
+    ends: BTreeMap<Instant, Vec<(Entity, Moment)>>,
+    spans: HashMap<Entity, Vec<Span>>,
+    pruned_until: PrunedUntil,
 }
 
 impl<T> Default for TimingData<T> {
@@ -177,6 +560,26 @@ impl<T> TimingData<T> {
             should_update: BitSet::new(),
             ends: BTreeMap::new(),
             starts: BTreeMap::new(),
+            spans: HashMap::new(),
+            pruned_until: PrunedUntil::default(),
+        }
+    }
+
+    // Same schedule, for whichever entities `entities` maps these ones to.
+    fn remapped(&self, entities: &HashMap<Entity, Entity>) -> TimingData<T> {
+        TimingData {
+            phantom_data: PhantomData,
+            should_update: BitSet::new(),
+            starts: remap_entries(&self.starts, entities),
+            ends: remap_entries(&self.ends, entities),
+            spans: self
+                .spans
+                .iter()
+                .filter_map(|(entity, spans)| {
+                    entities.get(entity).map(|&mapped| (mapped, spans.clone()))
+                })
+                .collect(),
+            pruned_until: self.pruned_until,
         }
     }
 
@@ -188,20 +591,76 @@ impl<T> TimingData<T> {
         self.should_update.add(entity.id());
     }
 
-    fn schedule(&mut self, entity: &Entity, time: &Timekeeper, duration: Duration) {
-        let (start, end) = match time.delta() {
-            DirectedTime::Past(_) => (time.now() - duration, time.now()),
-            _ => (time.now(), time.now() + duration),
+    fn schedule(
+        &mut self,
+        entity: &Entity,
+        time: &Timekeeper,
+        timeline: &Timeline,
+        duration: Duration,
+    ) -> Option<Duration> {
+        let duration = match time.to_global(entity, duration) {
+            Some(duration) => duration,
+            None => {
+                info!("{:?} is frozen, can't schedule", entity);
+                return None;
+            }
         };
+        let (start, end) = time.span(duration);
+        self.insert(
+            *entity,
+            Span {
+                start,
+                end,
+                scheduled: timeline.moment(time),
+                interruptions: Vec::new(),
+            },
+        );
+        info!("scheduled {:?} for {:?}-{:?}", entity, start, end);
+        Some(duration)
+    }
+
+    fn insert(&mut self, entity: Entity, span: Span) {
         self.starts
-            .entry(start)
+            .entry(span.start)
             .or_insert_with(Vec::new)
-            .push(entity.id());
+            .push((entity, span.scheduled));
         self.ends
-            .entry(end)
+            .entry(span.end)
             .or_insert_with(Vec::new)
-            .push(entity.id());
-        info!("scheduled {:?} for {:?}-{:?}", entity, start, end);
+            .push((entity, span.scheduled));
+        for &moment in &span.interruptions {
+            self.ends
+                .entry(moment.instant())
+                .or_insert_with(Vec::new)
+                .push((entity, moment));
+        }
+        self.spans.entry(entity).or_insert_with(Vec::new).push(span);
+    }
+
+    // Cuts the action `entity` is in the middle of short at `now()`. Whatever was done so far
+    // stands, and the part that won't happen anymore is returned.
+    fn interrupt(
+        &mut self,
+        entity: &Entity,
+        time: &Timekeeper,
+        timeline: &Timeline,
+    ) -> Option<Duration> {
+        let now = time.now();
+        let moment = timeline.moment(time);
+        let span = self
+            .spans
+            .get_mut(entity)?
+            .iter_mut()
+            .filter(|span| span.is_active(timeline, now))
+            .max_by_key(|span| span.start)?;
+        let unused = span.end(timeline).since(now);
+        span.interruptions.push(moment);
+        self.ends
+            .entry(now)
+            .or_insert_with(Vec::new)
+            .push((*entity, moment));
+        info!("interrupted {:?} at {:?}, {:?} unused", entity, now, unused);
+        Some(unused)
     }
 
     /*fn populate_schedule<C>(&mut self, join: JoinIter<(Entities, ReadStorage<C>)>, time: Timekeeper)
@@ -211,9 +670,182 @@ impl<T> TimingData<T> {
 
     }*/
 
+    fn prune(&mut self, limit: Instant) {
+        if !self.pruned_until.advance(limit) {
+            return;
+        }
+        self.pruned_until.split_off(&mut self.starts);
+        self.pruned_until.split_off(&mut self.ends);
+        self.spans.retain(|_, spans| {
+            spans.retain(|span| span.end >= limit);
+            !spans.is_empty()
+        });
+    }
+
     pub fn scheduled(&self) -> &BitSet {
         &self.should_update
     }
+
+    // Latest visible action of `entity` that has started but not yet ended at `now`.
+    pub fn active(
+        &self,
+        timeline: &Timeline,
+        entity: &Entity,
+        now: Instant,
+    ) -> Option<(Instant, Instant)> {
+        self.spans.get(entity).and_then(|spans| {
+            spans
+                .iter()
+                .filter(|span| span.is_active(timeline, now))
+                .map(|span| (span.start, span.end(timeline)))
+                .max()
+        })
+    }
+
+    // Every visible action as `(entity, start, end)`.
+    pub fn spans(&self, timeline: &Timeline) -> Vec<(Entity, Instant, Instant)> {
+        let mut visible = Vec::new();
+        for (entity, spans) in &self.spans {
+            for span in spans {
+                if timeline.is_visible(span.scheduled) {
+                    visible.push((*entity, span.start, span.end(timeline)));
+                }
+            }
+        }
+        visible
+    }
+
+    // Visible actions that ran their full course and ended in `(from, to]`, as `(entity, end)`.
+    pub fn completed(
+        &self,
+        timeline: &Timeline,
+        from: Instant,
+        to: Instant,
+    ) -> Vec<(Entity, Instant)> {
+        let mut completed = Vec::new();
+        for (&end, ended) in self.ends.range((Excluded(from), Included(to))) {
+            for &(entity, moment) in ended {
+                let finished = self.spans.get(&entity).map_or(false, |spans| {
+                    spans.iter().any(|span| {
+                        span.scheduled == moment && span.end == end && span.end(timeline) == end
+                    })
+                });
+                if finished && timeline.is_visible(moment) {
+                    completed.push((entity, end));
+                }
+            }
+        }
+        completed
+    }
+
+    pub fn progress(&self, timeline: &Timeline, entity: &Entity, now: Instant) -> Option<f32> {
+        self.active(timeline, entity, now).map(|(start, end)| {
+            duration_to_nanos(now.since(start)) as f32 / duration_to_nanos(end.since(start)) as f32
+        })
+    }
+
+    pub fn closest_future(&self, timeline: &Timeline, now: Instant) -> Option<Instant> {
+        let start = first_visible(timeline, self.starts.range((Excluded(now), Unbounded)));
+        let end = first_visible(timeline, self.ends.range((Excluded(now), Unbounded)));
+        start.into_iter().chain(end).min()
+    }
+
+    pub fn closest_past(&self, timeline: &Timeline, now: Instant) -> Option<Instant> {
+        let start = first_visible(timeline, self.starts.range(..now).rev());
+        let end = first_visible(timeline, self.ends.range(..now).rev());
+        start.into_iter().chain(end).max()
+    }
+}
+
+fn first_visible<'a, I>(timeline: &Timeline, mut entries: I) -> Option<Instant>
+where
+    I: Iterator<Item = (&'a Instant, &'a Vec<(Entity, Moment)>)>,
+{
+    entries
+        .find(|&(_, scheduled)| {
+            scheduled
+                .iter()
+                .any(|&(_, moment)| timeline.is_visible(moment))
+        })
+        .map(|(instant, _)| *instant)
+}
+
+fn remap_entries(
+    entries: &BTreeMap<Instant, Vec<(Entity, Moment)>>,
+    entities: &HashMap<Entity, Entity>,
+) -> BTreeMap<Instant, Vec<(Entity, Moment)>> {
+    entries
+        .iter()
+        .map(|(instant, scheduled)| {
+            let scheduled = scheduled
+                .iter()
+                .filter_map(|&(entity, moment)| {
+                    entities.get(&entity).map(|&mapped| (mapped, moment))
+                })
+                .collect();
+            (*instant, scheduled)
+        })
+        .collect()
+}
+
+type SpanReader = fn(&Resources) -> Vec<(Entity, Instant, Instant)>;
+type TimingCopier = fn(&Resources, &Resources, &HashMap<Entity, Entity>);
+
+// Lets debug tools and previews get at every `TimingData<T>` without knowing the `T`s.
+pub struct TimingRegistry {
+    readers: Vec<SpanReader>,
+    copiers: Vec<TimingCopier>,
+}
+
+impl Default for TimingRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TimingRegistry {
+    pub fn new() -> TimingRegistry {
+        TimingRegistry {
+            readers: Vec::new(),
+            copiers: Vec::new(),
+        }
+    }
+
+    fn register<T>(&mut self)
+    where
+        T: Send + Sync + 'static,
+    {
+        self.readers.push(read_spans::<T>);
+        self.copiers.push(copy_timing::<T>);
+    }
+
+    pub fn spans(&self, resources: &Resources) -> Vec<Vec<(Entity, Instant, Instant)>> {
+        self.readers
+            .iter()
+            .map(|reader| reader(resources))
+            .collect()
+    }
+
+    pub fn copy(&self, from: &Resources, to: &Resources, entities: &HashMap<Entity, Entity>) {
+        for copier in &self.copiers {
+            copier(from, to, entities);
+        }
+    }
+}
+
+fn copy_timing<T>(from: &Resources, to: &Resources, entities: &HashMap<Entity, Entity>)
+where
+    T: Send + Sync + 'static,
+{
+    *to.fetch_mut::<TimingData<T>>() = from.fetch::<TimingData<T>>().remapped(entities);
+}
+
+fn read_spans<T>(resources: &Resources) -> Vec<(Entity, Instant, Instant)>
+where
+    T: Send + Sync + 'static,
+{
+    let timeline = resources.fetch::<Timeline>();
+    resources.fetch::<TimingData<T>>().spans(&timeline)
 }
 
 pub struct TimingSystem<T> {
@@ -234,54 +866,64 @@ where
 {
     type SystemData = (
         Write<'a, Timekeeper>,
+        Read<'a, Timeline>,
         Entities<'a>,
         ReadStorage<'a, T>,
         Write<'a, TimingData<T>>,
     );
 
-    fn run(&mut self, (time, entity_s, timed_s, mut timing_data): Self::SystemData) {
+    fn run(&mut self, (mut time, timeline, entity_s, timed_s, mut timing_data): Self::SystemData) {
         timing_data.clear_update_flags();
         match time.delta() {
             DirectedTime::Still => (),
             _ => {
                 for (entity, _) in (&*entity_s, &timed_s).join() {
-                    timing_data.set_update_flag(&entity);
+                    if !time.is_frozen(&entity) {
+                        timing_data.set_update_flag(&entity);
+                    }
                 }
             }
         }
-        // TODO: Timekeeper::sim_closest_future, Timekeeper::sim_closest_past
-        /*match time.delta() {
-            DirectedTime::Still => (),
-            other => {
-                let (start, end) = match other {
-                    DirectedTime::Future(delta) => (time.now() - delta, time.now()),
-                    DirectedTime::Past(delta) => (time.now(), time.now() + delta),
-                    _ => unreachable!("Literally impossible."),
-                };
-                for (entity, _) in (&*entity_s, &timed_s).join() {
-                    timing_data.set_update_flag(&entity);
-                }
-            }
-        };*/
+        timing_data.prune(time.rewind_limit());
+        let now = time.now();
+        time.limit_horizon(
+            timing_data.closest_past(&timeline, now),
+            timing_data.closest_future(&timeline, now),
+        );
     }
 
     fn setup(&mut self, resources: &mut Resources) {
         Self::SystemData::setup(resources);
         resources.insert(TimingData::<T>::new());
+        <Write<TimingRegistry> as SystemData>::setup(resources);
+        resources.fetch_mut::<TimingRegistry>().register::<T>();
+    }
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        let remainder = a % b;
+        a = b;
+        b = remainder;
     }
+    a
 }
 
-fn mul_dur_by_factor<T: Copy + Into<f64>>(duration: Duration, factor: T) -> Duration {
-    let adjusted_s: f64 = duration.as_secs() as f64 * factor.into();
-    let mut adjusted_n: f64 = duration.subsec_nanos() as f64 * factor.into();
-    let trunc_s = adjusted_s.trunc();
-    let subsec = adjusted_s - trunc_s;
-    let mut adjusted_s = trunc_s as u64;
-    adjusted_n += subsec * 1_000_000_000.0;
-    let rem_n = adjusted_n % 1_000_000_000.0;
-    adjusted_s += (adjusted_n / 1_000_000_000.0).trunc() as u64;
-    let adjusted_n = rem_n as u32;
-    Duration::new(adjusted_s, adjusted_n)
+fn duration_to_nanos(duration: Duration) -> u64 {
+    duration.as_secs() * NANOS_PER_SEC + u64::from(duration.subsec_nanos())
+}
+
+// For drawing and ratios, where precision doesn't matter.
+pub fn seconds(duration: Duration) -> f32 {
+    duration.as_secs() as f32 + duration.subsec_nanos() as f32 / 1e9
+}
+
+fn nanos_to_duration(nanos: u64) -> Duration {
+    Duration::new(nanos / NANOS_PER_SEC, (nanos % NANOS_PER_SEC) as u32)
+}
+
+fn saturate(nanos: u128) -> u64 {
+    min(nanos, u128::from(u64::max_value())) as u64
 }
 
 #[cfg(test)]
@@ -435,19 +1077,399 @@ mod tests {
         );
     }
 
+    #[test]
+    fn horizon_clamping() {
+        let mut timekeeper = Timekeeper::new();
+        timekeeper.add_simulation_time(Duration::from_secs(8));
+        timekeeper.limit_horizon(None, Some(Instant(Duration::from_secs(3))));
+        timekeeper.limit_horizon(None, Some(Instant(Duration::from_secs(4))));
+        timekeeper.update_real_time(Duration::from_secs(5));
+        assert_eq!(
+            timekeeper.delta(),
+            DirectedTime::Future(Duration::from_secs(3))
+        );
+        timekeeper.update_real_time(Duration::from_secs(2));
+        assert_eq!(
+            timekeeper.delta(),
+            DirectedTime::Future(Duration::from_secs(2))
+        );
+        timekeeper.set_time_factor(-1.0);
+        timekeeper.limit_horizon(Some(Instant(Duration::from_secs(4))), None);
+        timekeeper.limit_horizon(Some(Instant(Duration::from_secs(6))), None);
+        timekeeper.limit_horizon(Some(Instant(Duration::from_secs(7))), None);
+        timekeeper.update_real_time(Duration::from_secs(2));
+        assert_eq!(
+            timekeeper.delta(),
+            DirectedTime::Past(Duration::from_secs(1))
+        );
+    }
+
+    #[test]
+    fn timing_data_horizon() {
+        let mut world = World::new();
+        let entity = world.create_entity().build();
+        let mut timekeeper = Timekeeper::new();
+        let timeline = Timeline::new();
+        let mut timing_data = TimingData::<()>::new();
+        let now = timekeeper.now();
+        assert_eq!(timing_data.closest_future(&timeline, now), None);
+        assert_eq!(timing_data.closest_past(&timeline, now), None);
+        timing_data.schedule(&entity, &timekeeper, &timeline, Duration::from_secs(2));
+        timekeeper.add_simulation_time(Duration::from_secs(1));
+        timekeeper.update_real_time(Duration::from_secs(1));
+        timing_data.schedule(&entity, &timekeeper, &timeline, Duration::from_secs(3));
+        let now = timekeeper.now();
+        assert_eq!(
+            timing_data.closest_future(&timeline, now),
+            Some(Instant(Duration::from_secs(2)))
+        );
+        assert_eq!(
+            timing_data.closest_past(&timeline, now),
+            Some(Instant(Duration::from_secs(0)))
+        );
+        timekeeper.add_simulation_time(Duration::from_secs(8));
+        timekeeper.limit_horizon(
+            timing_data.closest_past(&timeline, now),
+            timing_data.closest_future(&timeline, now),
+        );
+        timekeeper.update_real_time(Duration::from_secs(8));
+        assert_eq!(
+            timekeeper.delta(),
+            DirectedTime::Future(Duration::from_secs(1))
+        );
+        let now = timekeeper.now();
+        timekeeper.limit_horizon(
+            timing_data.closest_past(&timeline, now),
+            timing_data.closest_future(&timeline, now),
+        );
+        timekeeper.update_real_time(Duration::from_secs(8));
+        assert_eq!(
+            timekeeper.delta(),
+            DirectedTime::Future(Duration::from_secs(2))
+        );
+        let now = timekeeper.now();
+        assert_eq!(timing_data.closest_future(&timeline, now), None);
+    }
+
+    #[test]
+    fn timing_data_branches() {
+        let mut world = World::new();
+        let entity = world.create_entity().build();
+        let mut timekeeper = Timekeeper::new();
+        let mut timeline = Timeline::new();
+        let mut timing_data = TimingData::<()>::new();
+        timekeeper.add_simulation_time(Duration::from_secs(2));
+        timekeeper.update_real_time(Duration::from_secs(2));
+        timing_data.schedule(&entity, &timekeeper, &timeline, Duration::from_secs(2));
+        timekeeper.set_time_factor(-1.0);
+        timekeeper.add_simulation_time(Duration::from_secs(1));
+        timekeeper.update_real_time(Duration::from_secs(1));
+        let now = timekeeper.now();
+        assert_eq!(
+            timing_data.closest_future(&timeline, now),
+            Some(Instant(Duration::from_secs(2)))
+        );
+        timeline.fork(now);
+        assert_eq!(timing_data.closest_future(&timeline, now), None);
+    }
+
+    #[test]
+    fn timing_data_progress() {
+        let mut world = World::new();
+        let entity = world.create_entity().build();
+        let idle = world.create_entity().build();
+        let mut timekeeper = Timekeeper::new();
+        let timeline = Timeline::new();
+        let mut timing_data = TimingData::<()>::new();
+        timing_data.schedule(&entity, &timekeeper, &timeline, Duration::from_secs(4));
+        assert_eq!(
+            timing_data.progress(&timeline, &entity, timekeeper.now()),
+            Some(0.0)
+        );
+        timekeeper.add_simulation_time(Duration::from_secs(8));
+        timekeeper.update_real_time(Duration::from_secs(1));
+        assert_eq!(
+            timing_data.progress(&timeline, &entity, timekeeper.now()),
+            Some(0.25)
+        );
+        assert_eq!(
+            timing_data.progress(&timeline, &idle, timekeeper.now()),
+            None
+        );
+        timekeeper.update_real_time(Duration::from_secs(3));
+        assert_eq!(
+            timing_data.progress(&timeline, &entity, timekeeper.now()),
+            None
+        );
+        timekeeper.set_time_factor(-1.0);
+        timekeeper.update_real_time(Duration::from_secs(2));
+        assert_eq!(
+            timing_data.progress(&timeline, &entity, timekeeper.now()),
+            Some(0.5)
+        );
+    }
+
+    #[test]
+    fn timing_registry() {
+        let mut world = World::new();
+        let entity = world.create_entity().build();
+        let timekeeper = Timekeeper::new();
+        let timeline = Timeline::new();
+        let mut timing_data = TimingData::<()>::new();
+        timing_data.schedule(&entity, &timekeeper, &timeline, Duration::from_secs(2));
+        world.add_resource(timeline);
+        world.add_resource(timing_data);
+        let mut registry = TimingRegistry::new();
+        registry.register::<()>();
+        assert_eq!(
+            registry.spans(&world.res),
+            vec![vec![(
+                entity,
+                Instant::default(),
+                Instant::default() + Duration::from_secs(2)
+            )]]
+        );
+    }
+
+    #[test]
+    fn interruption() {
+        let mut world = World::new();
+        let entity = world.create_entity().build();
+        let mut timekeeper = Timekeeper::new();
+        let mut timeline = Timeline::new();
+        let mut timing_data = TimingData::<()>::new();
+        let root = timeline.current();
+        let start = timekeeper.now();
+        timekeeper.add_simulation_time(Duration::from_secs(4));
+        timing_data.schedule(&entity, &timekeeper, &timeline, Duration::from_secs(4));
+        timekeeper.update_real_time(Duration::from_secs(1));
+        timeline.fork(timekeeper.now());
+
+        assert_eq!(
+            timing_data.interrupt(&entity, &timekeeper, &timeline),
+            Some(Duration::from_secs(3))
+        );
+        timekeeper.refund_simulation_time(Duration::from_secs(3));
+        assert_eq!(
+            timekeeper.remaining_simulation_time(),
+            Duration::from_secs(0)
+        );
+        assert_eq!(timing_data.interrupt(&entity, &timekeeper, &timeline), None);
+        assert_eq!(
+            timing_data.spans(&timeline),
+            vec![(entity, start, timekeeper.now())]
+        );
+        assert_eq!(
+            timing_data.progress(&timeline, &entity, timekeeper.now()),
+            None
+        );
+
+        // Didn't happen on a branch that forked off before the interruption.
+        timeline.switch_to(root);
+        assert_eq!(
+            timing_data.progress(&timeline, &entity, timekeeper.now()),
+            Some(0.25)
+        );
+    }
+
+    #[test]
+    fn simulation_modes() {
+        let mut timekeeper = Timekeeper::new();
+        timekeeper.add_simulation_time(Duration::from_secs(1));
+        timekeeper.set_mode(SimulationMode::RealTime);
+        timekeeper.update_real_time(Duration::from_secs(3));
+        assert_eq!(
+            timekeeper.delta(),
+            DirectedTime::Future(Duration::from_secs(3))
+        );
+        assert_eq!(
+            timekeeper.remaining_simulation_time(),
+            Duration::from_secs(0)
+        );
+        timekeeper.set_time_factor(-1.0);
+        timekeeper.update_real_time(Duration::from_secs(5));
+        assert_eq!(
+            timekeeper.delta(),
+            DirectedTime::Past(Duration::from_secs(3))
+        );
+
+        timekeeper.set_mode(SimulationMode::TurnBased);
+        timekeeper.set_time_factor(1.0);
+        timekeeper.update_real_time(Duration::from_secs(1));
+        assert_eq!(timekeeper.delta(), DirectedTime::Still);
+    }
+
+    #[test]
+    fn history_pruning() {
+        let mut world = World::new();
+        let entity = world.create_entity().build();
+        let mut timekeeper = Timekeeper::new();
+        let timeline = Timeline::new();
+        let mut timing_data = TimingData::<()>::new();
+        timekeeper.set_history_horizon(Some(Duration::from_secs(2)));
+        timekeeper.add_simulation_time(Duration::from_secs(5));
+        for _ in 0..5 {
+            timing_data.schedule(&entity, &timekeeper, &timeline, Duration::from_secs(1));
+            timekeeper.update_real_time(Duration::from_secs(1));
+        }
+        assert_eq!(
+            timekeeper.rewind_limit(),
+            Instant::default() + Duration::from_secs(3)
+        );
+        timing_data.prune(timekeeper.rewind_limit());
+        assert_eq!(timing_data.starts.len(), 2);
+        assert_eq!(timing_data.ends.len(), 3);
+        assert_eq!(timing_data.spans(&timeline).len(), 3);
+
+        timekeeper.set_time_factor(-1.0);
+        timekeeper.add_simulation_time(Duration::from_secs(5));
+        timekeeper.update_real_time(Duration::from_secs(5));
+        assert_eq!(
+            timekeeper.delta(),
+            DirectedTime::Past(Duration::from_secs(2))
+        );
+        assert_eq!(timekeeper.now(), timekeeper.rewind_limit());
+    }
+
+    #[test]
+    fn pruning_bounds_memory() {
+        let mut world = World::new();
+        let entity = world.create_entity().build();
+        let mut timekeeper = Timekeeper::new();
+        let timeline = Timeline::new();
+        let mut timing_data = TimingData::<()>::new();
+        let step = Duration::from_millis(1);
+        timekeeper.set_history_horizon(Some(Duration::from_millis(100)));
+        let retained = |timing_data: &TimingData<()>| {
+            timing_data.starts.values().map(Vec::len).sum::<usize>()
+                + timing_data.ends.values().map(Vec::len).sum::<usize>()
+                + timing_data.spans.values().map(Vec::len).sum::<usize>()
+        };
+        for action in 0..1_000_000 {
+            timing_data.schedule(&entity, &timekeeper, &timeline, step);
+            timekeeper.add_simulation_time(step);
+            timekeeper.update_real_time(step);
+            timing_data.prune(timekeeper.rewind_limit());
+            if action % 1_000 == 0 {
+                assert!(retained(&timing_data) <= 3 * 101);
+            }
+        }
+        assert_eq!(
+            timekeeper.now(),
+            Instant::default() + Duration::from_secs(1_000)
+        );
+        // Everything still within reach of rewinding is kept.
+        assert!(timing_data.spans[&entity].len() >= 100);
+        assert!(retained(&timing_data) <= 3 * 101);
+    }
+
     #[test]
     fn duration_multiplication() {
         assert_eq!(
-            mul_dur_by_factor(Duration::new(4, 600_000_000), 0.5),
+            TimeFactor::from(0.5).scale(Duration::new(4, 600_000_000)),
             Duration::new(2, 300_000_000)
         );
         assert_eq!(
-            mul_dur_by_factor(Duration::new(4, 600_000_000), 2.0),
+            TimeFactor::from(2.0).scale(Duration::new(4, 600_000_000)),
             Duration::new(9, 200_000_000)
         );
         assert_eq!(
-            mul_dur_by_factor(Duration::new(5, 600_000_000), 0.5),
+            TimeFactor::from(0.5).scale(Duration::new(5, 600_000_000)),
             Duration::new(2, 800_000_000)
         );
+        assert_eq!(
+            TimeFactor::new(-1, 3).scale(Duration::from_secs(1)),
+            Duration::new(0, 333_333_333)
+        );
+        assert_eq!(
+            TimeFactor::new(3_000_000_001, 3_000_000_000).scale(Duration::from_secs(10)),
+            Duration::new(10, 3)
+        );
+        assert_eq!(
+            TimeFactor::new(1 << 40, 1).scale(Duration::from_secs(5)),
+            nanos_to_duration(u64::max_value())
+        );
+    }
+
+    #[test]
+    fn time_factor_reduction() {
+        assert_eq!(TimeFactor::from(0.5), TimeFactor::new(1, 2));
+        assert_eq!(TimeFactor::from(-5.0), TimeFactor::new(-10, 2));
+        assert_eq!(TimeFactor::new(0, 7), TimeFactor::new(0, 1));
+        assert_eq!(TimeFactor::new(6, 4).numerator(), 3);
+        assert_eq!(TimeFactor::new(6, 4).denominator(), 2);
+    }
+
+    #[test]
+    fn exact_ticks() {
+        let mut timekeeper = Timekeeper::new();
+        let start = timekeeper.now();
+        timekeeper.add_simulation_time(Duration::from_secs(8));
+        for _ in 0..TICKS_PER_SECOND {
+            timekeeper.tick();
+        }
+        assert_eq!(
+            start.compare_to(timekeeper.now()),
+            DirectedTime::Future(Duration::from_secs(1))
+        );
+        timekeeper.set_exact_time_factor(TimeFactor::new(1, 3));
+        for _ in 0..TICKS_PER_SECOND * 3 {
+            timekeeper.tick();
+        }
+        assert_eq!(
+            start.compare_to(timekeeper.now()),
+            DirectedTime::Future(Duration::from_secs(2))
+        );
+        timekeeper.set_exact_time_factor(TimeFactor::new(-1, 3));
+        for _ in 0..TICKS_PER_SECOND * 3 {
+            timekeeper.tick();
+        }
+        assert_eq!(timekeeper.ticks(), u64::from(TICKS_PER_SECOND) * 7);
+        assert_eq!(
+            start.compare_to(timekeeper.now()),
+            DirectedTime::Future(Duration::from_secs(1))
+        );
+    }
+
+    #[test]
+    fn span_near_start() {
+        let mut timekeeper = Timekeeper::new();
+        timekeeper.add_simulation_time(Duration::from_secs(1));
+        timekeeper.update_real_time(Duration::from_secs(1));
+        timekeeper.set_time_factor(-1.0);
+        timekeeper.scrub_to(Some(Instant::default()));
+        timekeeper.update_real_time(Duration::from_secs(1));
+        assert_eq!(
+            timekeeper.delta(),
+            DirectedTime::Past(Duration::from_secs(1))
+        );
+        assert_eq!(
+            timekeeper.span(Duration::from_secs(2)),
+            (Instant::default(), Instant::default())
+        );
+    }
+
+    #[test]
+    fn zero_factor() {
+        let mut timekeeper = Timekeeper::new();
+        timekeeper.add_simulation_time(Duration::from_secs(8));
+        timekeeper.set_time_factor(0.0);
+        timekeeper.update_real_time(Duration::from_secs(2));
+        assert_eq!(timekeeper.delta(), DirectedTime::Still);
+    }
+
+    #[test]
+    fn rewind_lock() {
+        let mut timekeeper = Timekeeper::new();
+        timekeeper.set_time_factor(-1.0);
+        timekeeper.lock_rewind(true);
+        assert_eq!(timekeeper.exact_time_factor(), TimeFactor::new(0, 1));
+        timekeeper.set_time_factor(-2.0);
+        assert_eq!(timekeeper.exact_time_factor(), TimeFactor::new(0, 1));
+        timekeeper.set_time_factor(2.0);
+        assert_eq!(timekeeper.exact_time_factor(), TimeFactor::new(2, 1));
+        timekeeper.lock_rewind(false);
+        timekeeper.set_time_factor(-2.0);
+        assert_eq!(timekeeper.exact_time_factor(), TimeFactor::new(-2, 1));
     }
 }
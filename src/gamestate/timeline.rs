@@ -0,0 +1,294 @@
+use specs::prelude::*;
+use std::cmp::min;
+
+use super::time::*;
+
+pub fn module_systems<'a, 'b>(builder: DispatcherBuilder<'a, 'b>) -> DispatcherBuilder<'a, 'b> {
+    // Energy is paid back against the frontier from before this tick.
+    builder.with(TimelineSystem, "timeline", &["chrono_energy"])
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct BranchId(usize);
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct Moment {
+    branch: BranchId,
+    instant: Instant,
+    // Orders moments that share an instant, see `Timeline::fork()`.
+    sequence: u64,
+}
+
+impl Moment {
+    pub fn branch(&self) -> BranchId {
+        self.branch
+    }
+
+    pub fn instant(&self) -> Instant {
+        self.instant
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Branch {
+    parent: Option<BranchId>,
+    fork: Instant,
+    // Ancestor's moments at `fork` are only visible up to this sequence number.
+    fork_sequence: u64,
+    frontier: Instant,
+    // Every instant the frontier advanced to, and the sequence number it got there with.
+    advances: Vec<(Instant, u64)>,
+}
+
+impl Branch {
+    pub fn parent(&self) -> Option<BranchId> {
+        self.parent
+    }
+
+    pub fn fork(&self) -> Instant {
+        self.fork
+    }
+
+    pub fn frontier(&self) -> Instant {
+        self.frontier
+    }
+}
+
+#[derive(Clone)]
+pub struct Timeline {
+    branches: Vec<Branch>,
+    current: BranchId,
+    switched_from: Option<BranchId>,
+    // Goes up every dispatch, so later moments at the same instant sort after earlier ones.
+    sequence: u64,
+}
+
+impl Default for Timeline {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Timeline {
+    pub fn new() -> Timeline {
+        Timeline {
+            branches: vec![Branch {
+                parent: None,
+                fork: Instant::default(),
+                fork_sequence: 0,
+                frontier: Instant::default(),
+                advances: vec![(Instant::default(), 0)],
+            }],
+            current: BranchId(0),
+            switched_from: None,
+            sequence: 0,
+        }
+    }
+
+    pub fn current(&self) -> BranchId {
+        self.current
+    }
+
+    pub fn moment(&self, time: &Timekeeper) -> Moment {
+        self.moment_at(time.now())
+    }
+
+    pub fn moment_at(&self, instant: Instant) -> Moment {
+        Moment {
+            branch: self.current,
+            instant,
+            sequence: self.sequence,
+        }
+    }
+
+    pub fn frontier(&self) -> Instant {
+        self.branches[self.current.0].frontier
+    }
+
+    pub fn branch(&self, branch: BranchId) -> Option<&Branch> {
+        self.branches.get(branch.0)
+    }
+
+    pub fn branches(&self) -> impl Iterator<Item = (BranchId, &Branch)> {
+        self.branches
+            .iter()
+            .enumerate()
+            .map(|(index, branch)| (BranchId(index), branch))
+    }
+
+    // Whatever the current branch did at `at` after first getting there, e.g. actions it
+    // started, doesn't carry over; only what led up to it, e.g. arrivals, does.
+    pub fn fork(&mut self, at: Instant) -> BranchId {
+        let branch = BranchId(self.branches.len());
+        let fork_sequence = self.branches[self.current.0]
+            .advances
+            .iter()
+            .find(|&&(instant, _)| instant >= at)
+            .map_or(self.sequence, |&(_, sequence)| sequence);
+        self.branches.push(Branch {
+            parent: Some(self.current),
+            fork: at,
+            fork_sequence,
+            frontier: at,
+            advances: vec![(at, fork_sequence)],
+        });
+        info!("forked {:?} from {:?} at {:?}", branch, self.current, at);
+        if self.switched_from.is_none() {
+            self.switched_from = Some(self.current);
+        }
+        self.current = branch;
+        branch
+    }
+
+    pub fn switch_to(&mut self, branch: BranchId) -> bool {
+        if branch == self.current || self.branch(branch).is_none() {
+            return false;
+        }
+        info!("switched from {:?} to {:?}", self.current, branch);
+        if self.switched_from.is_none() {
+            self.switched_from = Some(self.current);
+        }
+        self.current = branch;
+        true
+    }
+
+    pub fn switched_from(&self) -> Option<BranchId> {
+        self.switched_from
+    }
+
+    pub fn is_visible(&self, moment: Moment) -> bool {
+        self.is_visible_from(self.current, moment)
+    }
+
+    // Something recorded on an ancestor is only visible up to where the path forked off it.
+    pub fn is_visible_from(&self, viewer: BranchId, moment: Moment) -> bool {
+        let mut branch = viewer;
+        let mut limit: Option<(Instant, u64)> = None;
+        loop {
+            if branch == moment.branch {
+                return limit.map_or(true, |limit| (moment.instant, moment.sequence) <= limit);
+            }
+            let node = &self.branches[branch.0];
+            match node.parent {
+                Some(parent) => {
+                    let fork = (node.fork, node.fork_sequence);
+                    limit = Some(limit.map_or(fork, |limit| min(limit, fork)));
+                    branch = parent;
+                }
+                None => return false,
+            }
+        }
+    }
+
+    fn advance(&mut self, now: Instant) {
+        self.sequence += 1;
+        let current = &mut self.branches[self.current.0];
+        if now > current.frontier {
+            current.frontier = now;
+            current.advances.push((now, self.sequence));
+        }
+        self.switched_from = None;
+    }
+
+    // Can't fork before `limit`, so where the frontier was before it doesn't matter anymore.
+    fn prune(&mut self, limit: Instant) {
+        for branch in &mut self.branches {
+            let keep = branch
+                .advances
+                .iter()
+                .position(|&(instant, _)| instant >= limit)
+                .unwrap_or_else(|| branch.advances.len());
+            branch.advances.drain(..keep);
+        }
+    }
+}
+
+struct TimelineSystem;
+
+impl<'a> System<'a> for TimelineSystem {
+    type SystemData = (Read<'a, Timekeeper>, Write<'a, Timeline>);
+
+    fn run(&mut self, (time, mut timeline): Self::SystemData) {
+        timeline.advance(time.now());
+        timeline.prune(time.rewind_limit());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn visibility() {
+        let mut time = Timekeeper::new();
+        let mut timeline = Timeline::new();
+        let root = timeline.current();
+        time.add_simulation_time(Duration::from_secs(8));
+        time.update_real_time(Duration::from_secs(4));
+        timeline.advance(time.now());
+        let root_late = timeline.moment(&time);
+        time.set_time_factor(-1.0);
+        time.update_real_time(Duration::from_secs(2));
+        let root_early = timeline.moment_at(time.now() - Duration::from_secs(1));
+        let root_at_fork = timeline.moment(&time);
+        let child = timeline.fork(time.now());
+        let child_moment = timeline.moment(&time);
+        assert_eq!(timeline.frontier(), time.now());
+        assert_eq!(timeline.switched_from(), Some(root));
+        timeline.advance(time.now());
+        assert!(timeline.is_visible(root_early));
+        assert!(timeline.is_visible(root_at_fork));
+        assert!(!timeline.is_visible(root_late));
+        assert!(timeline.is_visible(child_moment));
+        assert!(timeline.is_visible_from(root, root_late));
+        assert!(!timeline.is_visible_from(root, child_moment));
+
+        time.update_real_time(Duration::from_secs(1));
+        let grandchild = timeline.fork(time.now());
+        timeline.advance(time.now());
+        assert_eq!(timeline.branch(grandchild).unwrap().parent(), Some(child));
+        assert!(timeline.is_visible(root_early));
+        assert!(!timeline.is_visible(child_moment));
+        assert!(timeline.is_visible_from(child, child_moment));
+
+        assert!(timeline.switch_to(root));
+        assert!(!timeline.switch_to(root));
+        assert_eq!(timeline.switched_from(), Some(grandchild));
+        timeline.advance(time.now());
+        assert_eq!(timeline.switched_from(), None);
+        assert_eq!(timeline.branches().count(), 3);
+    }
+
+    #[test]
+    fn same_instant_forks() {
+        let mut time = Timekeeper::new();
+        let mut timeline = Timeline::new();
+        let root = timeline.current();
+        let spawned = timeline.moment(&time);
+        // Some dispatches later, still at the same instant, an action gets started.
+        timeline.advance(time.now());
+        let started = timeline.moment(&time);
+        time.add_simulation_time(Duration::from_secs(2));
+        time.update_real_time(Duration::from_secs(1));
+        timeline.advance(time.now());
+        let arrived = timeline.moment(&time);
+        timeline.advance(time.now());
+        let restarted = timeline.moment(&time);
+        time.update_real_time(Duration::from_secs(1));
+        timeline.advance(time.now());
+
+        timeline.fork(Instant::default());
+        assert!(timeline.is_visible(spawned));
+        assert!(!timeline.is_visible(started));
+        assert!(timeline.is_visible_from(root, started));
+
+        assert!(timeline.switch_to(root));
+        timeline.fork(arrived.instant());
+        assert!(timeline.is_visible(arrived));
+        assert!(!timeline.is_visible(restarted));
+        // Forking again right where the branch started picks up from the same point.
+        timeline.fork(arrived.instant());
+        assert!(timeline.is_visible(arrived));
+        assert!(!timeline.is_visible(restarted));
+    }
+}
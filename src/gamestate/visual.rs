@@ -1,10 +1,13 @@
 use ggez::graphics::Color;
 use specs::prelude::*;
 
+use super::history::Historical;
 use assets::DrawableHandle;
 
-#[derive(Component, Debug)]
+#[derive(Component, Debug, Clone)]
 pub struct BaseSprite {
     pub drawable: DrawableHandle,
     pub color: Color,
 }
+
+impl Historical for BaseSprite {}
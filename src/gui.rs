@@ -1 +1,202 @@
-use specs::prelude::*;
+use ggez::graphics::{self, Color, DrawMode, Mesh, Rect, Text};
+use ggez::{Context, GameResult};
+use nalgebra as na;
+use specs::World;
+use std::collections::BTreeSet;
+use std::time::Duration;
+
+use gamestate::{seconds, ChronoEnergy, Instant, Timekeeper, Timeline, TimingRegistry};
+
+const HUD_MARGIN: f32 = 10.0;
+const ENERGY_BAR: (f32, f32) = (100.0, 8.0);
+
+const TIMELINE_HEIGHT: f32 = 60.0;
+const TIMELINE_MARGIN: f32 = 10.0;
+const TIMELINE_ROW: f32 = 6.0;
+const MARKER_WIDTH: f32 = 2.0;
+// One color per registered `TimingData<T>`, in registration order.
+const MARKER_COLORS: [[f32; 4]; 4] = [
+    [0.0, 1.0, 0.0, 1.0],
+    [0.0, 0.5, 1.0, 1.0],
+    [1.0, 0.5, 0.0, 1.0],
+    [1.0, 0.0, 1.0, 1.0],
+];
+
+pub fn draw_pause_overlay(ctx: &mut Context) -> GameResult {
+    let screen = graphics::screen_coordinates(ctx);
+    let shade = Mesh::new_rectangle(ctx, DrawMode::Fill, screen)?;
+    graphics::draw(
+        ctx,
+        &shade,
+        (na::Point2::origin(), Color::from([0.0, 0.0, 0.0, 0.5])),
+    )?;
+    let text = Text::new("PAUSED");
+    let (width, height) = text.dimensions(ctx);
+    graphics::draw(
+        ctx,
+        &text,
+        (
+            na::Point2::new(
+                screen.x + (screen.w - width as f32) / 2.0,
+                screen.y + (screen.h - height as f32) / 2.0,
+            ),
+            Color::from([1.0, 1.0, 1.0, 1.0]),
+        ),
+    )
+}
+
+pub fn draw_notice(ctx: &mut Context, text: &str) -> GameResult {
+    let screen = graphics::screen_coordinates(ctx);
+    let text = Text::new(text);
+    let (width, _) = text.dimensions(ctx);
+    graphics::draw(
+        ctx,
+        &text,
+        (
+            na::Point2::new(
+                screen.x + (screen.w - width as f32) / 2.0,
+                screen.y + HUD_MARGIN,
+            ),
+            Color::from([1.0, 0.3, 0.3, 1.0]),
+        ),
+    )
+}
+
+pub fn draw_hud(ctx: &mut Context, world: &World) -> GameResult {
+    let screen = graphics::screen_coordinates(ctx);
+    let energy = world.read_resource::<ChronoEnergy>();
+    let text = Text::new("CHRONO");
+    let (width, height) = text.dimensions(ctx);
+    let (x, y) = (screen.x + HUD_MARGIN, screen.y + HUD_MARGIN);
+    graphics::draw(
+        ctx,
+        &text,
+        (na::Point2::new(x, y), Color::from([1.0, 1.0, 1.0, 1.0])),
+    )?;
+    let bar = Rect::new(
+        x + width as f32 + HUD_MARGIN,
+        y + (height as f32 - ENERGY_BAR.1) / 2.0,
+        ENERGY_BAR.0,
+        ENERGY_BAR.1,
+    );
+    draw_rect(ctx, bar, [0.1, 0.1, 0.1, 0.8])?;
+    let color = if energy.is_empty() {
+        [1.0, 0.0, 0.0, 1.0]
+    } else {
+        [0.5, 0.5, 1.0, 1.0]
+    };
+    draw_rect(
+        ctx,
+        Rect::new(bar.x, bar.y, bar.w * energy.fraction(), bar.h),
+        color,
+    )
+}
+
+// Every scheduled action as a start and an end marker, one row per entity, along with
+// `now()` and the remaining simulation time.
+pub fn draw_timeline_overlay(ctx: &mut Context, world: &World) -> GameResult {
+    let bounds = timeline_bounds(ctx);
+    let length = timeline_length(world);
+    let to_x = |instant: Instant| bounds.x + bounds.w * instant_seconds(instant) / length;
+    draw_rect(ctx, bounds, [0.1, 0.1, 0.1, 0.8])?;
+
+    let spans = world.read_resource::<TimingRegistry>().spans(&world.res);
+    let rows = spans
+        .iter()
+        .flat_map(|spans| spans.iter().map(|&(entity, _, _)| entity))
+        .collect::<BTreeSet<_>>();
+    for (kind, spans) in spans.iter().enumerate() {
+        let color = MARKER_COLORS[kind % MARKER_COLORS.len()];
+        let faded = [color[0], color[1], color[2], 0.4];
+        for &(entity, start, end) in spans {
+            let row = rows.iter().position(|&row| row == entity).unwrap_or(0);
+            let y = bounds.y + TIMELINE_ROW * (row as f32 + 1.0);
+            if y + TIMELINE_ROW > bounds.y + bounds.h {
+                continue;
+            }
+            let (start_x, end_x) = (to_x(start), to_x(end));
+            draw_rect(
+                ctx,
+                Rect::new(start_x, y + TIMELINE_ROW / 2.0, end_x - start_x, 1.0),
+                faded,
+            )?;
+            draw_rect(
+                ctx,
+                Rect::new(start_x, y, MARKER_WIDTH, TIMELINE_ROW),
+                color,
+            )?;
+            draw_rect(
+                ctx,
+                Rect::new(end_x - MARKER_WIDTH, y, MARKER_WIDTH, TIMELINE_ROW),
+                color,
+            )?;
+        }
+    }
+
+    let time = world.read_resource::<Timekeeper>();
+    let now_x = to_x(time.now());
+    let remaining_x = to_x(time.now() + time.remaining_simulation_time());
+    draw_rect(
+        ctx,
+        Rect::new(now_x, bounds.y, remaining_x - now_x, TIMELINE_ROW / 2.0),
+        [1.0, 1.0, 0.0, 1.0],
+    )?;
+    draw_rect(
+        ctx,
+        Rect::new(now_x - MARKER_WIDTH / 2.0, bounds.y, MARKER_WIDTH, bounds.h),
+        [1.0, 1.0, 1.0, 1.0],
+    )
+}
+
+// Where on the timeline `(x, y)` is, if it's on the timeline at all.
+pub fn timeline_instant(ctx: &mut Context, world: &World, x: f32, y: f32) -> Option<Instant> {
+    let bounds = timeline_bounds(ctx);
+    if !bounds.contains(na::Point2::new(x, y)) {
+        return None;
+    }
+    let offset = (x - bounds.x) / bounds.w * timeline_length(world);
+    Some(Instant::default() + Duration::from_nanos((offset * 1e9) as u64))
+}
+
+fn timeline_bounds(ctx: &mut Context) -> Rect {
+    let screen = graphics::screen_coordinates(ctx);
+    Rect::new(
+        screen.x + TIMELINE_MARGIN,
+        screen.y + screen.h - TIMELINE_HEIGHT - TIMELINE_MARGIN,
+        screen.w - 2.0 * TIMELINE_MARGIN,
+        TIMELINE_HEIGHT,
+    )
+}
+
+// Seconds shown by the timeline; enough for everything simulated or scheduled so far.
+fn timeline_length(world: &World) -> f32 {
+    let time = world.read_resource::<Timekeeper>();
+    let timeline = world.read_resource::<Timeline>();
+    let latest_end = world
+        .read_resource::<TimingRegistry>()
+        .spans(&world.res)
+        .iter()
+        .flat_map(|spans| spans.iter().map(|&(_, _, end)| instant_seconds(end)))
+        .fold(0.0, f32::max);
+    [
+        1.0,
+        instant_seconds(timeline.frontier()),
+        instant_seconds(time.now() + time.remaining_simulation_time()),
+        latest_end,
+    ]
+    .iter()
+    .cloned()
+    .fold(0.0, f32::max)
+}
+
+fn instant_seconds(instant: Instant) -> f32 {
+    seconds(instant.since(Instant::default()))
+}
+
+fn draw_rect(ctx: &mut Context, rect: Rect, color: [f32; 4]) -> GameResult {
+    if rect.w <= 0.0 || rect.h <= 0.0 {
+        return Ok(());
+    }
+    let mesh = Mesh::new_rectangle(ctx, DrawMode::Fill, rect)?;
+    graphics::draw(ctx, &mesh, (na::Point2::origin(), Color::from(color)))
+}
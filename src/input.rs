@@ -4,6 +4,7 @@ use std::collections::HashMap;
 
 use gamestate::Direction;
 use gamestate::GameCommand;
+use gamestate::TimeControl;
 use keymod::KeyMod;
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
@@ -33,12 +34,27 @@ pub enum Command {
 pub enum AppCommand {
     Exit,
     Pause,
+    Time(TimeControl),
+    ToggleTimeline,
+    Seek,
+    Preview(GameCommand),
+    Record,
+    SpawnClone,
 }
 
 type Bindings = HashMap<Input, Vec<(KeyMod, Command)>>;
 
 pub struct InputHandler {
     bindings: Bindings,
+    paused: bool,
+    time_control: Option<TimeControl>,
+    // Key that started the time control that lasts only while it's held.
+    held_time_control: Option<KeyCode>,
+    show_timeline: bool,
+    seek: Option<(f32, f32)>,
+    preview: Option<GameCommand>,
+    recording: bool,
+    spawn_clone: bool,
 }
 
 impl Default for InputHandler {
@@ -50,6 +66,56 @@ impl Default for InputHandler {
                 KeyMod::CTRL,
                 Command::App(AppCommand::Exit),
             )
+            .bind(
+                Input::Key(KeyCode::P),
+                KeyMod::NONE,
+                Command::App(AppCommand::Pause),
+            )
+            .bind(
+                Input::Key(KeyCode::Z),
+                KeyMod::NONE,
+                Command::App(AppCommand::Time(TimeControl::Rewind)),
+            )
+            .bind(
+                Input::Key(KeyCode::X),
+                KeyMod::NONE,
+                Command::App(AppCommand::Time(TimeControl::FastForward)),
+            )
+            .bind(
+                Input::Key(KeyCode::Z),
+                KeyMod::SHIFT,
+                Command::App(AppCommand::Time(TimeControl::StepBack)),
+            )
+            .bind(
+                Input::Key(KeyCode::X),
+                KeyMod::SHIFT,
+                Command::App(AppCommand::Time(TimeControl::StepForward)),
+            )
+            .bind(
+                Input::Key(KeyCode::F3),
+                KeyMod::NONE,
+                Command::App(AppCommand::ToggleTimeline),
+            )
+            .bind(
+                Input::Mouse(MouseButton::Left),
+                KeyMod::NONE,
+                Command::App(AppCommand::Seek),
+            )
+            .bind(
+                Input::Key(KeyCode::R),
+                KeyMod::NONE,
+                Command::App(AppCommand::Record),
+            )
+            .bind(
+                Input::Key(KeyCode::C),
+                KeyMod::NONE,
+                Command::App(AppCommand::SpawnClone),
+            )
+            .bind(
+                Input::Key(KeyCode::Space),
+                KeyMod::NONE,
+                Command::Game(GameCommand::Stop),
+            )
             .bind(
                 Input::Key(KeyCode::W),
                 KeyMod::NONE,
@@ -69,6 +135,36 @@ impl Default for InputHandler {
                 Input::Key(KeyCode::D),
                 KeyMod::NONE,
                 Command::Game(GameCommand::Move(Direction::E)),
+            )
+            .bind(
+                Input::Key(KeyCode::Comma),
+                KeyMod::SHIFT,
+                Command::Game(GameCommand::Move(Direction::U)),
+            )
+            .bind(
+                Input::Key(KeyCode::Period),
+                KeyMod::SHIFT,
+                Command::Game(GameCommand::Move(Direction::D)),
+            )
+            .bind(
+                Input::Key(KeyCode::W),
+                KeyMod::SHIFT,
+                Command::App(AppCommand::Preview(GameCommand::Move(Direction::N))),
+            )
+            .bind(
+                Input::Key(KeyCode::A),
+                KeyMod::SHIFT,
+                Command::App(AppCommand::Preview(GameCommand::Move(Direction::W))),
+            )
+            .bind(
+                Input::Key(KeyCode::S),
+                KeyMod::SHIFT,
+                Command::App(AppCommand::Preview(GameCommand::Move(Direction::S))),
+            )
+            .bind(
+                Input::Key(KeyCode::D),
+                KeyMod::SHIFT,
+                Command::App(AppCommand::Preview(GameCommand::Move(Direction::E))),
             );
         handler
     }
@@ -78,6 +174,14 @@ impl InputHandler {
     pub fn new() -> InputHandler {
         InputHandler {
             bindings: Bindings::new(),
+            paused: false,
+            time_control: None,
+            held_time_control: None,
+            show_timeline: false,
+            seek: None,
+            preview: None,
+            recording: false,
+            spawn_clone: false,
         }
     }
 
@@ -107,10 +211,45 @@ impl InputHandler {
         self
     }
 
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn take_time_control(&mut self) -> Option<TimeControl> {
+        self.time_control.take()
+    }
+
+    pub fn is_timeline_shown(&self) -> bool {
+        self.show_timeline
+    }
+
+    pub fn take_seek(&mut self) -> Option<(f32, f32)> {
+        self.seek.take()
+    }
+
+    pub fn take_preview(&mut self) -> Option<GameCommand> {
+        self.preview.take()
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording
+    }
+
+    pub fn take_spawn_clone(&mut self) -> bool {
+        let spawn_clone = self.spawn_clone;
+        self.spawn_clone = false;
+        spawn_clone
+    }
+
+    // While paused, only app-level bindings are considered.
     fn resolve(&self, input: Input, keymods: KeyMod) -> Option<Command> {
         if let Some(bound_action_bunch) = self.bindings.get(&input) {
             for bound_action in bound_action_bunch {
-                if keymods.contains(bound_action.0) {
+                let allowed = match bound_action.1 {
+                    Command::App(_) => true,
+                    _ => !self.paused,
+                };
+                if allowed && keymods.contains(bound_action.0) {
                     return Some(bound_action.1);
                 }
             }
@@ -119,6 +258,7 @@ impl InputHandler {
     }
 
     fn execute(
+        &mut self,
         ctx: &mut Context,
         action: Option<Command>,
         input: InputExtra,
@@ -128,7 +268,23 @@ impl InputHandler {
             match action {
                 Command::App(command) => match command {
                     AppCommand::Exit => ctx.quit(),
-                    AppCommand::Pause => unimplemented!(),
+                    AppCommand::Pause => {
+                        self.paused = !self.paused;
+                        info!("paused: {}", self.paused);
+                    }
+                    AppCommand::Time(control) => self.time_control = Some(control),
+                    AppCommand::ToggleTimeline => self.show_timeline = !self.show_timeline,
+                    AppCommand::Preview(command) => self.preview = Some(command),
+                    AppCommand::Record => {
+                        self.recording = !self.recording;
+                        info!("recording: {}", self.recording);
+                    }
+                    AppCommand::SpawnClone => self.spawn_clone = true,
+                    AppCommand::Seek => {
+                        if let InputExtra::XY(x, y) = input {
+                            self.seek = Some((x, y));
+                        }
+                    }
                 },
                 Command::Game(command) => return Some(command),
                 #[cfg(test)]
@@ -146,7 +302,38 @@ impl InputHandler {
         repeat: bool,
     ) -> Option<GameCommand> {
         let command = self.resolve(Input::Key(key), mods);
-        InputHandler::execute(ctx, command, InputExtra::RepeatedKey(repeat))
+        self.hold_time_control(key, command);
+        self.execute(ctx, command, InputExtra::RepeatedKey(repeat))
+    }
+
+    fn hold_time_control(&mut self, key: KeyCode, command: Option<Command>) {
+        if let Some(Command::App(AppCommand::Time(control))) = command {
+            self.held_time_control = match control {
+                TimeControl::Rewind | TimeControl::FastForward => Some(key),
+                _ => None,
+            };
+        }
+    }
+
+    pub fn mouse_button_down_event(
+        &mut self,
+        ctx: &mut Context,
+        button: MouseButton,
+        mods: KeyMod,
+        x: f32,
+        y: f32,
+    ) -> Option<GameCommand> {
+        let command = self.resolve(Input::Mouse(button), mods);
+        self.execute(ctx, command, InputExtra::XY(x, y))
+    }
+
+    // Rewind and fast-forward only last while their key is held, whatever modifiers are
+    // held when it's let go.
+    pub fn key_up_event(&mut self, key: KeyCode) {
+        if self.held_time_control == Some(key) {
+            self.held_time_control = None;
+            self.time_control = Some(TimeControl::Play);
+        }
     }
 }
 
@@ -208,4 +395,40 @@ mod tests {
             Some(Command::Test(2))
         );
     }
+
+    #[test]
+    fn held_time_control() {
+        let mut handler = InputHandler::default();
+        let command = handler.resolve(Input::Key(KeyCode::Z), KeyMod::NONE);
+        handler.hold_time_control(KeyCode::Z, command);
+        handler.key_up_event(KeyCode::X);
+        assert_eq!(handler.take_time_control(), None);
+        handler.key_up_event(KeyCode::Z);
+        assert_eq!(handler.take_time_control(), Some(TimeControl::Play));
+
+        // Steps are over by the time their key is let go.
+        let command = handler.resolve(Input::Key(KeyCode::Z), KeyMod::SHIFT);
+        handler.hold_time_control(KeyCode::Z, command);
+        handler.key_up_event(KeyCode::Z);
+        assert_eq!(handler.take_time_control(), None);
+    }
+
+    #[test]
+    fn paused_resolution() {
+        let mut handler = InputHandler::new();
+        handler
+            .bind(Input::Test, KeyMod::NONE, Command::Test(0))
+            .bind(Input::Test, KeyMod::SHIFT, Command::App(AppCommand::Pause));
+        handler.paused = true;
+        assert_eq!(handler.resolve(Input::Test, KeyMod::NONE), None);
+        assert_eq!(
+            handler.resolve(Input::Test, KeyMod::SHIFT),
+            Some(Command::App(AppCommand::Pause))
+        );
+        handler.paused = false;
+        assert_eq!(
+            handler.resolve(Input::Test, KeyMod::NONE),
+            Some(Command::Test(0))
+        );
+    }
 }
@@ -10,6 +10,7 @@ extern crate shred_derive;
 #[macro_use]
 extern crate log;
 extern crate nalgebra;
+extern crate rand;
 extern crate specs;
 #[macro_use]
 extern crate specs_derive;
@@ -17,6 +18,8 @@ extern crate specs_derive;
 use ggez::conf::{WindowMode, WindowSetup};
 use ggez::event;
 use ggez::{ContextBuilder, GameResult};
+use std::str::FromStr;
+use std::time::Duration;
 
 mod app;
 mod assets;
@@ -26,7 +29,7 @@ mod input;
 mod keymod;
 mod renderer;
 
-fn wrapped() -> GameResult {
+fn wrapped(seed: u64, echo_fade: Option<Duration>, mode: gamestate::SimulationMode) -> GameResult {
     let w_dim = nalgebra::Vector2::new(640, 480);
 
     let (ctx, events_loop) = &mut ContextBuilder::new("SpelunkingSpellwright", "Ratys")
@@ -39,11 +42,23 @@ fn wrapped() -> GameResult {
         )
         .build()?;
 
-    let state = &mut app::App::new(ctx)?;
+    let state = &mut app::App::new(ctx, seed)?;
+    if let Some(fade) = echo_fade {
+        state.set_echo_fade(fade);
+    }
+    state.set_simulation_mode(mode);
     event::run(ctx, events_loop, state)
 }
 
+fn flag_value<T: FromStr>(args: &[String], flag: &str) -> Option<T> {
+    args.iter()
+        .position(|arg| arg == flag)
+        .and_then(|index| args.get(index + 1))
+        .and_then(|value| value.parse().ok())
+}
+
 fn main() {
+    // Logs go to stderr, out of the way of anything printed for the user.
     #[cfg(debug_assertions)]
     {
         use fern::colors::{Color, ColoredLevelConfig};
@@ -65,12 +80,30 @@ fn main() {
             })
             .level_for("gfx_device_gl", log::LevelFilter::Warn)
             .level(log::LevelFilter::Trace)
-            .chain(std::io::stdout())
+            .chain(std::io::stderr())
             .apply()
             .unwrap();
     }
 
-    if let Err(e) = wrapped() {
+    // `--seed <n>` picks the cave, `--dump-cave` prints it instead of starting the game,
+    // `--echo-fade <ms>` sets how long rewound positions linger on screen, `--real-time`
+    // keeps time flowing without waiting on the player.
+    let args = std::env::args().collect::<Vec<_>>();
+    let seed =
+        flag_value(&args, "--seed").unwrap_or_else(|| chrono::Local::now().timestamp() as u64);
+    let echo_fade = flag_value(&args, "--echo-fade").map(Duration::from_millis);
+    let mode = if args.iter().any(|arg| arg == "--real-time") {
+        gamestate::SimulationMode::RealTime
+    } else {
+        gamestate::SimulationMode::TurnBased
+    };
+    if args.iter().any(|arg| arg == "--dump-cave") {
+        let cave = gamestate::Cave::generate(seed, &gamestate::CaveParams::default());
+        println!("seed {}\n{}", seed, cave.to_ascii());
+        return;
+    }
+
+    if let Err(e) = wrapped(seed, echo_fade, mode) {
         error!("{}", e);
     }
 }
@@ -1,29 +1,173 @@
-use ggez::graphics;
+use ggez::graphics::{self, Color, DrawMode, Mesh, MeshBuilder};
 use ggez::{Context, GameResult};
 use nalgebra as na;
 use specs::{Join, World};
 
+use std::time::Duration;
+
 use assets::Assets;
 use gamestate::BaseSprite;
-use gamestate::Position;
+use gamestate::Ghost;
+use gamestate::{
+    seconds, DirectedTime, Dungeon, Journal, Movable, Position, TileType, Timekeeper, Timeline,
+    TimingData,
+};
 
 pub const TILE_SIZE_PX: (f32, f32) = (10.0, 10.0);
+pub const DEFAULT_ECHO_FADE_MS: u64 = 500;
+const GHOST_ALPHA: f32 = 0.35;
+const ECHO_ALPHA: f32 = 0.5;
+
+// Meshes of the active level, one per tile type; rebuilt only once the dungeon changes or
+// another level becomes active.
+#[derive(Default)]
+pub struct MapMeshes {
+    built_for: Option<(i32, u64)>,
+    meshes: Vec<(Color, Mesh)>,
+}
+
+// Tiles are centered on their coordinates, same as sprites. Only the active level is drawn.
+pub fn render_map(ctx: &mut Context, world: &World, cache: &mut MapMeshes) -> GameResult {
+    let dungeon = world.read_resource::<Dungeon>();
+    let key = (dungeon.active(), dungeon.revision());
+    if cache.built_for != Some(key) {
+        cache.meshes.clear();
+        if let Some(level) = dungeon.level(dungeon.active()) {
+            let mut builders: Vec<(TileType, MeshBuilder)> = Vec::new();
+            for ((x, y), tile_type) in level.map.tiles() {
+                let index = match builders.iter().position(|&(ty, _)| ty == tile_type) {
+                    Some(index) => index,
+                    None => {
+                        builders.push((tile_type, MeshBuilder::new()));
+                        builders.len() - 1
+                    }
+                };
+                let (x, y) = tile_to_screen(x as f32, y as f32);
+                let (w, h) = (TILE_SIZE_PX.0 / 2.0, TILE_SIZE_PX.1 / 2.0);
+                builders[index].1.polygon(
+                    DrawMode::Fill,
+                    &[
+                        na::Point2::new(x - w, y - h),
+                        na::Point2::new(x + w, y - h),
+                        na::Point2::new(x + w, y + h),
+                        na::Point2::new(x - w, y + h),
+                    ],
+                );
+            }
+            for (tile_type, builder) in builders {
+                cache
+                    .meshes
+                    .push((tile_color(tile_type), builder.build(ctx)?));
+            }
+        }
+        cache.built_for = Some(key);
+    }
+    for &(color, ref mesh) in &cache.meshes {
+        graphics::draw(ctx, mesh, (na::Point2::origin(), color))?;
+    }
+    Ok(())
+}
+
+fn tile_color(tile_type: TileType) -> Color {
+    Color::from(match tile_type {
+        TileType::Floor => [0.15, 0.15, 0.15, 1.0],
+        TileType::Wall => [0.5, 0.45, 0.4, 1.0],
+        TileType::Chasm => [0.02, 0.0, 0.05, 1.0],
+        TileType::Water => [0.1, 0.2, 0.6, 1.0],
+        TileType::StairsUp => [0.4, 0.7, 0.4, 1.0],
+        TileType::StairsDown => [0.7, 0.4, 0.2, 1.0],
+        TileType::Rope => [0.6, 0.5, 0.2, 1.0],
+        TileType::Hole => [0.0, 0.0, 0.0, 1.0],
+    })
+}
 
 pub fn render(ctx: &mut Context, world: &World, assets: &Assets) -> GameResult {
+    let time = world.read_resource::<Timekeeper>();
+    let timeline = world.read_resource::<Timeline>();
+    let movable_timing = world.read_resource::<TimingData<Movable>>();
+    let dungeon = world.read_resource::<Dungeon>();
+    let entity_s = world.entities();
     let pos_s = world.read_storage::<Position>();
+    let mov_s = world.read_storage::<Movable>();
     let vis_s = world.read_storage::<BaseSprite>();
-    for (pos, vis) in (&pos_s, &vis_s).join() {
+    for (entity, pos, vis) in (&*entity_s, &pos_s, &vis_s).join() {
+        if pos.z() != dungeon.active() {
+            continue;
+        }
+        let (x, y) = tile_to_screen(pos.x() as f32, pos.y() as f32);
+        // Slide towards the target tile while a move is in flight.
+        let (dx, dy) = match (
+            mov_s.get(entity),
+            movable_timing.progress(&timeline, &entity, time.now()),
+        ) {
+            (Some(movable), Some(progress)) => {
+                let (dx, dy) = movable.direction().offset();
+                tile_to_screen(dx as f32 * progress, dy as f32 * progress)
+            }
+            _ => (0.0, 0.0),
+        };
         graphics::draw(
             ctx,
             assets.fetch_drawable(vis.drawable),
-            (
-                na::Point2::new(
-                    pos.x() as f32 * TILE_SIZE_PX.0,
-                    pos.y() as f32 * TILE_SIZE_PX.1,
-                ),
-                vis.color,
-            ),
+            (na::Point2::new(x + dx, y + dy), vis.color),
         )?;
     }
     Ok(())
 }
+
+pub fn render_ghosts(
+    ctx: &mut Context,
+    world: &World,
+    ghosts: &[Ghost],
+    assets: &Assets,
+) -> GameResult {
+    let active = world.read_resource::<Dungeon>().active();
+    for ghost in ghosts.iter().filter(|ghost| ghost.position.z() == active) {
+        let (x, y) = tile_to_screen(ghost.position.x() as f32, ghost.position.y() as f32);
+        let mut color = ghost.sprite.color;
+        color.a *= GHOST_ALPHA;
+        graphics::draw(
+            ctx,
+            assets.fetch_drawable(ghost.sprite.drawable),
+            (na::Point2::new(x, y), color),
+        )?;
+    }
+    Ok(())
+}
+
+// While rewinding, whatever positions are being undone linger for `fade` as echoes.
+pub fn render_echoes(
+    ctx: &mut Context,
+    world: &World,
+    assets: &Assets,
+    fade: Duration,
+) -> GameResult {
+    let time = world.read_resource::<Timekeeper>();
+    if let DirectedTime::Past(_) = time.delta() {
+        let timeline = world.read_resource::<Timeline>();
+        let journal = world.read_resource::<Journal<Position>>();
+        let active = world.read_resource::<Dungeon>().active();
+        let vis_s = world.read_storage::<BaseSprite>();
+        let now = time.now();
+        for (instant, entity, pos) in journal.recorded(&timeline, now, now + fade) {
+            if pos.z() != active {
+                continue;
+            }
+            if let Some(vis) = vis_s.get(entity) {
+                let (x, y) = tile_to_screen(pos.x() as f32, pos.y() as f32);
+                let mut color = vis.color;
+                color.a *= ECHO_ALPHA * (1.0 - seconds(instant.since(now)) / seconds(fade));
+                graphics::draw(
+                    ctx,
+                    assets.fetch_drawable(vis.drawable),
+                    (na::Point2::new(x, y), color),
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn tile_to_screen(x: f32, y: f32) -> (f32, f32) {
+    (x * TILE_SIZE_PX.0, y * TILE_SIZE_PX.1)
+}